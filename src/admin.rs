@@ -1,4 +1,4 @@
-use actix_web::dev::ServiceRequest;
+use actix_web::{dev::ServiceRequest, http::header, HttpRequest};
 use actix_web_httpauth::extractors::{
     bearer::{BearerAuth, Config},
     AuthenticationError,
@@ -18,3 +18,17 @@ pub(crate) async fn validate_admin(
         Err((AuthenticationError::from(config).into(), req))
     }
 }
+
+/// Whether a request outside the authenticated scope carries the admin bearer token,
+/// for public endpoints that show admins a little more.
+pub(crate) fn is_admin(req: &HttpRequest) -> bool {
+    let Ok(token) = std::env::var("ADMIN_BEARER_TOKEN") else {
+        return false;
+    };
+
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|credentials| credentials == token)
+}
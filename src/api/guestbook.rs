@@ -1,23 +1,51 @@
-use actix_web::{get, http::header::ContentType, post, web, Either, HttpRequest, HttpResponse};
-use minijinja::render;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use actix_web::{
+    delete, get,
+    http::header::{
+        ContentDisposition, ContentType, DispositionParam, DispositionType, ETag, EntityTag,
+        Header, HttpDate, IfModifiedSince, IfNoneMatch, LastModified,
+    },
+    patch, post, web, Either, HttpRequest, HttpResponse,
+};
+use minijinja::context;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use utoipa::{IntoParams, ToSchema};
 
 use crate::{
+    admin::is_admin,
     blog::deploy_blog,
-    error::ApiError,
+    client_ip::client_ip,
+    error::{ApiError, Conflict, NotFound},
     guestbook::{
-        entry::Entry,
-        queries::{get_single_entry, get_undeleted_entries, put_guestbook_entry},
+        actions::{hard_delete_entry, reply_to_entry, GuestbookAction},
+        avatar::identicon_svg,
+        backup::{import_entries, parse_jsonl, ExportFormat, ImportReport},
+        cursor::Cursor,
+        edit::EditRejection,
+        entry::{Entry, EntryStatus},
+        feed::{render_feed, FeedFormat, FEED_SIZE},
+        moderation::Submission,
+        queries::{
+            get_filtered_entries, get_undeleted_entries, AdminFilter, Order, PageRequest,
+            StatusFilter,
+        },
+        reactions::Reaction,
+        store::{EntryCounts, EntryEdit},
+        validation::validate_reply,
+        views::{AdminEntry, DetailedEntry, PublicEntry},
     },
-    slack::send_slack_message,
+    slack::{channel::SlackChannel, send_slack, send_slack_message},
 };
 
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub(crate) struct GuestbookForm {
+    /// Limited to 600 characters, counting each emoji as one.
     #[schema(example = "John Appleseed", max_length = 600)]
     pub name: String,
 
+    /// Limited to 1200 characters, counting each emoji as one.
     #[schema(example = "Just stopping by to say hello!", max_length = 1200)]
     pub message: String,
 
@@ -30,6 +58,12 @@ pub(crate) struct GuestbookForm {
     #[serde(default)]
     #[schema(example = true)]
     pub qa: bool,
+
+    /// A honeypot: the guestbook form hides this field, so only bots fill it in.
+    /// Submissions that include it are rejected.
+    #[serde(default)]
+    #[schema(example = json!(null))]
+    pub phone: Option<String>,
 }
 
 /// Create a Guestbook Entry
@@ -39,13 +73,34 @@ pub(crate) struct GuestbookForm {
 /// Successfully submitting this API will also redeploy my blog, which displays
 /// all the guestbook entries.
 ///
+/// When the server runs with `GUESTBOOK_REQUIRE_APPROVAL=true`, new entries are
+/// created with a `pending` status instead. They aren't listed, and the blog isn't
+/// redeployed, until an admin approves them.
+///
 /// Passing the `qa: true` option will still create an entry in the backing database,
 /// and all side effects will still take place, but the new entry will not be displayed
 /// on my blog.
+///
+/// Names and messages are trimmed and can't be blank. URLs without a scheme get
+/// `https://` added, and only http(s) URLs are accepted. Invalid submissions get a 400
+/// response whose body has a `reason` of `invalid_fields` and a `fields` list with the
+/// `field`, `code`, and `message` of every problem found.
+///
+/// Valid submissions then pass through a spam filter. A rejected submission gets a 400
+/// response whose body has a `reason` of `honeypot`, `banned_content`,
+/// `too_many_links`, or `too_many_submissions`.
+///
+/// The response includes an `edit_token`, which lets the submitter fix their entry
+/// with `PATCH /guestbook/{id}` for a little while. It's never returned again, so
+/// only the submitter ever has it.
+///
+/// Requests sent by htmx (with an `HX-Request` header) get HTML back instead: a
+/// thank-you message on success, or the form with the errors listed above it. Both
+/// come back with a 200 so that htmx swaps them in.
 #[utoipa::path(
     request_body(content = inline(GuestbookForm)),
     responses(
-        (status=200, description = "Success response", body = inline(Entry))
+        (status=200, description = "Success response", body = inline(PostGuestbookResponse))
     ),
     tag = "Guestbook"
 )]
@@ -56,26 +111,186 @@ pub(crate) async fn post_guestbook(
     state: web::Data<crate::AppState>,
 ) -> Result<HttpResponse, ApiError> {
     let guestbook_form = data.into_inner();
-    let mut guestbook_entry = Entry::try_from(guestbook_form)?;
+    let htmx = is_htmx_request(&req);
+
+    let mut guestbook_entry = match screen_submission(&req, &state, &guestbook_form) {
+        Ok(entry) => entry,
+        Err(err) if htmx => {
+            return render_html(
+                &state,
+                "guestbook/form.html",
+                context! { form => guestbook_form, errors => err.messages() },
+            );
+        }
+        Err(err) => return Err(err),
+    };
+
+    if state.guestbook_require_approval {
+        guestbook_entry.status = EntryStatus::Pending;
+    }
 
-    put_guestbook_entry(&state.dynamodb, &guestbook_entry).await?;
+    state.guestbook.put_entry(&guestbook_entry).await?;
 
     let _ = send_slack_message(&guestbook_entry.slack_api_request(req.peer_addr())).await;
 
-    if !guestbook_entry.qa {
+    if guestbook_entry.is_published() && !guestbook_entry.qa {
         let _ = deploy_blog().await;
     }
 
-    if guestbook_entry.qa {
-        guestbook_entry.push_ser_option("serialize_qa");
+    if htmx {
+        return render_html(
+            &state,
+            "guestbook/success.html",
+            context! {
+                published => guestbook_entry.is_published() && !guestbook_entry.qa,
+                entry => PublicEntry::from(guestbook_entry),
+            },
+        );
+    }
+
+    Ok(HttpResponse::Ok().json(PostGuestbookResponse {
+        edit_token: state.guestbook_edit_tokens.issue(&guestbook_entry),
+        entry: guestbook_entry.into(),
+    }))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct PostGuestbookResponse {
+    #[serde(flatten)]
+    entry: DetailedEntry,
+
+    /// Pass this to `PATCH /guestbook/{id}` to edit the entry once, shortly after
+    /// creating it.
+    #[schema(example = "3f2a9c0e5b7d41e8a6c4f1b2d3e5a7c9b1d3f5e7a9c1b3d5f7e9a1c3b5d7f9e1")]
+    edit_token: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct PatchGuestbookForm {
+    /// The `edit_token` from the response that created the entry.
+    pub edit_token: String,
+
+    #[serde(flatten)]
+    pub form: GuestbookForm,
+}
+
+/// Edit a Guestbook Entry
+///
+/// Replaces the name, message, email, and URL of the guestbook entry with the given ID,
+/// then returns the updated object. The `qa` field can't be changed.
+///
+/// This needs the `edit_token` returned when the entry was created, which works for one
+/// edit, for a while after that (15 minutes, unless configured otherwise). Edits pass
+/// through the same checks and spam filter as new entries. When the server requires
+/// approval, an edited entry goes back to `pending`.
+#[utoipa::path(
+    request_body(content = inline(PatchGuestbookForm)),
+    responses(
+        (status=200, description = "Success response", body = inline(DetailedEntry)),
+        (status=403, description = "The edit token is wrong, or the entry can no longer be edited"),
+        (status=409, description = "The edit token was already used"),
+    ),
+    tag = "Guestbook"
+)]
+#[patch("/guestbook/{id}")]
+pub(crate) async fn patch_guestbook_entry(
+    req: HttpRequest,
+    path: web::Path<uuid::Uuid>,
+    data: web::Json<PatchGuestbookForm>,
+    state: web::Data<crate::AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let entry_id = path.into_inner();
+    let PatchGuestbookForm { edit_token, form } = data.into_inner();
+
+    let entry = state.guestbook.get_entry(&entry_id).await?;
+    if entry.deleted_at.is_some() {
+        return Err(
+            anyhow::Error::new(NotFound(format!("No entry found with ID {entry_id}"))).into(),
+        );
+    }
+
+    state
+        .guestbook_edit_tokens
+        .verify(&entry, &edit_token, chrono::Utc::now())?;
+
+    let edited = screen_submission(&req, &state, &form)?;
+    let was_published = entry.is_published();
+
+    let edit = EntryEdit {
+        id: entry_id,
+        name: edited.name,
+        message: edited.message,
+        url: edited.url,
+        email: edited.email,
+        edited_at: chrono::Utc::now(),
+        unapprove: state.guestbook_require_approval && entry.status == EntryStatus::Approved,
+    };
+
+    // The store only saves the edit if nothing else has edited the entry since it was
+    // read, so that two requests with the same token can't both get through.
+    let entry = match state.guestbook.edit_entry(&edit).await {
+        Ok(entry) => entry,
+        Err(err) if err.is::<Conflict>() => return Err(EditRejection::AlreadyUsed.into()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let _ = send_slack_message(&entry.slack_api_request(req.peer_addr())).await;
+
+    if (was_published || entry.is_published()) && !entry.qa {
+        let _ = deploy_blog().await;
     }
-    Ok(HttpResponse::Ok().json(&guestbook_entry))
+
+    Ok(HttpResponse::Ok().json(DetailedEntry::from(entry)))
+}
+
+/// Validates a submission and runs it through the spam filter, then turns it into an
+/// entry.
+fn screen_submission(
+    req: &HttpRequest,
+    state: &crate::AppState,
+    guestbook_form: &GuestbookForm,
+) -> Result<Entry, ApiError> {
+    let entry = Entry::try_from(guestbook_form.clone())?;
+
+    state.guestbook_moderation.check(&Submission {
+        form: guestbook_form,
+        ip: client_ip(req),
+    })?;
+
+    Ok(entry)
+}
+
+/// Whether the request was made by htmx, which wants an HTML fragment back.
+fn is_htmx_request(req: &HttpRequest) -> bool {
+    req.headers().contains_key("HX-Request")
+}
+
+fn render_html(
+    state: &crate::AppState,
+    template: &str,
+    ctx: impl Serialize,
+) -> Result<HttpResponse, ApiError> {
+    let body = state.templates.render(template, ctx)?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(body))
 }
 
 #[derive(Debug, Deserialize, IntoParams)]
 pub(crate) struct GetGuestbookQueryParameters {
     pub after: Option<uuid::Uuid>,
 
+    /// The maximum number of entries to return, up to 100.
+    pub limit: Option<usize>,
+
+    /// A `next_cursor` or `prev_cursor` value from a previous response.
+    pub cursor: Option<String>,
+
+    /// `asc` (oldest first) or `desc` (newest first). Defaults to `asc`, or to
+    /// `desc` when `htmx` is set.
+    #[param(inline)]
+    pub order: Option<Order>,
+
     #[serde(default)]
     pub qa: bool,
 
@@ -99,30 +314,65 @@ struct GetGuestbookResponse {
         "message": "Woooooo",
         "name": "Mat"
       }]))]
-    items: Vec<Entry>,
+    items: Vec<PublicEntry>,
 
+    /// The number of entries in `items`.
     #[schema(example = "10")]
+    page_count: usize,
+
+    /// The number of entries this listing has across all of its pages. Includes QA
+    /// entries only when the `qa` query parameter is set.
+    #[schema(example = "42")]
+    visible_total: usize,
+
+    /// The number of deleted entries. Only included for admin requests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "3")]
+    deleted_total: Option<usize>,
+
+    /// The number of undeleted QA entries. Only included for admin requests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "5")]
+    qa_total: Option<usize>,
+
+    /// Use `page_count` instead.
+    #[schema(example = "10", deprecated)]
     count: usize,
 
-    #[schema(example = "15")]
+    /// The number of entries in the database, including deleted and QA entries. Use
+    /// `visible_total` instead.
+    #[schema(example = "50", deprecated)]
     total_count: usize,
+
+    #[schema(example = "eyJjIjoiMjAyNC0wNC0wNVQxNjoxMTowMy42NTdaIn0")]
+    next_cursor: Option<String>,
+
+    prev_cursor: Option<String>,
 }
 
 /// List Guestbook Entries
 ///
 /// List all guestbook entries in order of creation date.
 ///
+/// Passing `order=desc` lists the newest entries first.
+///
 /// Passing the `after` query parameter lets you only display entries after the given ID,
 /// not including the entry whose ID was passed in.
 ///
+/// Passing the `limit` query parameter caps the number of entries returned. When there
+/// are more entries on either side of the page, the response includes a `next_cursor`
+/// and/or a `prev_cursor`; pass either one back as the `cursor` query parameter (with
+/// the same `limit` and `order`) to fetch the neighbouring page.
+///
 /// Passing the `qa` query parameter will include all entries, including the ones
 /// submitted with the `QA` field, in the response.
 ///
 /// Passing with the `htmx` query parameter will render the listed entries as a set of
-/// HTML `<li>` elements.
+/// HTML `<li>` elements, newest first unless `order` says otherwise.
 ///
-/// There is no way to display deleted guestbook entries; however, the `total_count`
-/// field in the response will include deleted entries. This was probably a mistake.
+/// Deleted guestbook entries are only listed by `GET /admin/guestbook`. When the
+/// request carries the admin bearer token, the response also counts deleted and QA
+/// entries.
 #[utoipa::path(
     params(GetGuestbookQueryParameters),
     responses(
@@ -132,33 +382,233 @@ struct GetGuestbookResponse {
 )]
 #[get("/guestbook")]
 pub(crate) async fn get_guestbook(
+    req: HttpRequest,
     query: web::Query<GetGuestbookQueryParameters>,
     state: web::Data<crate::AppState>,
 ) -> Result<HttpResponse, ApiError> {
-    let (total_count, mut guestbook_entries) =
-        get_undeleted_entries(&state.dynamodb, query.after, query.qa).await?;
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(Cursor::decode)
+        .transpose()
+        .map_err(|_| ApiError::bad_request("Invalid cursor"))?;
+
+    let page = get_undeleted_entries(
+        state.guestbook.as_ref(),
+        &PageRequest {
+            after: query.after,
+            cursor,
+            limit: query.limit,
+            order: query
+                .order
+                .unwrap_or(if query.htmx { Order::Desc } else { Order::Asc }),
+        },
+        query.qa,
+    )
+    .await?;
 
+    let guestbook_entries = page
+        .entries
+        .into_iter()
+        .map(PublicEntry::from)
+        .collect::<Vec<_>>();
     let count = &guestbook_entries.len();
+    let is_admin = is_admin(&req);
 
     if query.htmx {
-        guestbook_entries.reverse();
-        let r = render!(HTMX_GUESTBOOK_LIST_TEMPLATE, entries => guestbook_entries );
-        Ok(HttpResponse::Ok().content_type(ContentType::html()).body(r))
+        render_html(
+            &state,
+            "guestbook/entries.html",
+            context! { entries => guestbook_entries },
+        )
     } else {
         Ok(HttpResponse::Ok().json(GetGuestbookResponse {
             items: guestbook_entries,
+            page_count: *count,
+            visible_total: page.counts.visible(query.qa),
+            deleted_total: is_admin.then_some(page.counts.deleted),
+            qa_total: is_admin.then_some(page.counts.qa),
             count: *count,
-            total_count,
+            total_count: page.counts.total,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
         }))
     }
 }
 
+#[derive(Debug, Deserialize, IntoParams)]
+pub(crate) struct GetGuestbookPageQueryParameters {
+    #[serde(default)]
+    pub qa: bool,
+}
+
+/// Guestbook Page
+///
+/// Renders the whole guestbook as an HTML page: the form for signing it, followed by
+/// every entry, newest first. The form submits with htmx.
+///
+/// Passing the `qa` query parameter includes entries submitted with the `QA` field.
+#[utoipa::path(
+    params(GetGuestbookPageQueryParameters),
+    responses(
+        (status=200, description = "Success response", content_type = "text/html", body = String)
+    ),
+    tag = "Guestbook"
+)]
+#[get("/guestbook.html")]
+pub(crate) async fn get_guestbook_page(
+    query: web::Query<GetGuestbookPageQueryParameters>,
+    state: web::Data<crate::AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let page = get_undeleted_entries(
+        state.guestbook.as_ref(),
+        &PageRequest {
+            order: Order::Desc,
+            ..Default::default()
+        },
+        query.qa,
+    )
+    .await?;
+
+    render_html(
+        &state,
+        "guestbook.html",
+        context! {
+            entries => page.entries.into_iter().map(PublicEntry::from).collect::<Vec<_>>(),
+            form => context! {},
+        },
+    )
+}
+
+/// Guestbook Form
+///
+/// Renders an empty guestbook form as an HTML fragment, for htmx to swap in after an
+/// entry has been submitted.
+#[utoipa::path(
+    responses(
+        (status=200, description = "Success response", content_type = "text/html", body = String)
+    ),
+    tag = "Guestbook"
+)]
+#[get("/guestbook/form")]
+pub(crate) async fn get_guestbook_form(
+    state: web::Data<crate::AppState>,
+) -> Result<HttpResponse, ApiError> {
+    render_html(
+        &state,
+        "guestbook/form.html",
+        context! { form => context! {} },
+    )
+}
+
+/// Guestbook Atom Feed
+///
+/// An Atom feed of the newest guestbook entries on my blog, newest first. Entries link
+/// to their author's website when they left one.
+///
+/// Supports conditional requests: pass back the `ETag` as `If-None-Match`, or the
+/// `Last-Modified` as `If-Modified-Since`, to get a 304 when nothing has changed.
+#[utoipa::path(
+    responses(
+        (status=200, description = "Success response", content_type = "application/atom+xml", body = String),
+        (status=304, description = "The feed hasn't changed"),
+    ),
+    tag = "Guestbook"
+)]
+#[get("/guestbook/feed.atom")]
+pub(crate) async fn get_guestbook_atom_feed(
+    req: HttpRequest,
+    state: web::Data<crate::AppState>,
+) -> Result<HttpResponse, ApiError> {
+    guestbook_feed(&req, &state, FeedFormat::Atom).await
+}
+
+/// Guestbook RSS Feed
+///
+/// The same feed as `GET /guestbook/feed.atom`, as RSS 2.0.
+#[utoipa::path(
+    responses(
+        (status=200, description = "Success response", content_type = "application/rss+xml", body = String),
+        (status=304, description = "The feed hasn't changed"),
+    ),
+    tag = "Guestbook"
+)]
+#[get("/guestbook/feed.rss")]
+pub(crate) async fn get_guestbook_rss_feed(
+    req: HttpRequest,
+    state: web::Data<crate::AppState>,
+) -> Result<HttpResponse, ApiError> {
+    guestbook_feed(&req, &state, FeedFormat::Rss).await
+}
+
+async fn guestbook_feed(
+    req: &HttpRequest,
+    state: &crate::AppState,
+    format: FeedFormat,
+) -> Result<HttpResponse, ApiError> {
+    let page = get_undeleted_entries(
+        state.guestbook.as_ref(),
+        &PageRequest {
+            limit: Some(FEED_SIZE),
+            order: Order::Desc,
+            ..Default::default()
+        },
+        false,
+    )
+    .await?;
+
+    let feed = render_feed(&state.templates, format, &page.entries)?;
+
+    // Hashing the body catches deletions and edits, which don't move Last-Modified.
+    let etag = EntityTag::new_strong(hex::encode(&Sha256::digest(feed.body.as_bytes())[..16]));
+
+    // HTTP dates only have whole seconds.
+    let last_modified = feed
+        .last_modified
+        .map(|time| UNIX_EPOCH + Duration::from_secs(time.timestamp().max(0) as u64));
+
+    if is_not_modified(req, &etag, last_modified) {
+        return Ok(HttpResponse::NotModified()
+            .insert_header(ETag(etag))
+            .finish());
+    }
+
+    let mut response = HttpResponse::Ok();
+    response
+        .content_type(format.content_type())
+        .insert_header(ETag(etag));
+    if let Some(last_modified) = last_modified {
+        response.insert_header(LastModified(HttpDate::from(last_modified)));
+    }
+
+    Ok(response.body(feed.body))
+}
+
+/// Whether the request's conditional headers say the client's copy is still fresh.
+/// `If-None-Match` wins over `If-Modified-Since` when both are sent.
+fn is_not_modified(req: &HttpRequest, etag: &EntityTag, last_modified: Option<SystemTime>) -> bool {
+    match IfNoneMatch::parse(req) {
+        Ok(IfNoneMatch::Any) => return true,
+        Ok(IfNoneMatch::Items(tags)) if !tags.is_empty() => {
+            return tags.iter().any(|tag| tag.weak_eq(etag))
+        }
+        _ => {}
+    }
+
+    match (IfModifiedSince::parse(req), last_modified) {
+        (Ok(IfModifiedSince(since)), Some(last_modified)) => {
+            last_modified <= SystemTime::from(since)
+        }
+        _ => false,
+    }
+}
+
 /// Get a Single Guestbook Entry
 ///
 /// Returns a single guestbook entry based on the ID.
 #[utoipa::path(
     responses(
-        (status=200, description = "Success response", body = inline(Entry))
+        (status=200, description = "Success response", body = inline(DetailedEntry))
     ),
     tag = "Guestbook"
 )]
@@ -168,15 +618,35 @@ pub(crate) async fn get_guestbook_entry(
     state: web::Data<crate::AppState>,
 ) -> Result<HttpResponse, ApiError> {
     let entry_id = path.into_inner();
-    let mut entry = get_single_entry(&state.dynamodb, &entry_id).await?;
-    entry.push_ser_option("serialize_deleted_at");
-    entry.push_ser_option("serialize_qa");
-    Ok(HttpResponse::Ok().json(&entry))
+    let entry = state.guestbook.get_entry(&entry_id).await?;
+    Ok(HttpResponse::Ok().json(DetailedEntry::from(entry)))
+}
+
+/// Guestbook Entry Avatar
+///
+/// The identicon for the guestbook entry with the given ID, as an SVG. Entries whose
+/// author left no email use this as their `avatar_url`, and Gravatar falls back to it
+/// for emails it doesn't know.
+///
+/// The image only depends on the ID, so it can be cached forever.
+#[utoipa::path(
+    responses(
+        (status=200, description = "Success response", content_type = "image/svg+xml", body = String)
+    ),
+    tag = "Guestbook"
+)]
+#[get("/guestbook/{id}/avatar.svg")]
+pub(crate) async fn get_guestbook_avatar(path: web::Path<uuid::Uuid>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("image/svg+xml")
+        .insert_header(("Cache-Control", "public, max-age=31536000, immutable"))
+        .body(identicon_svg(&path.into_inner()))
 }
 
 /// Delete a Guestbook Entry
 ///
 /// Deletes the guestbook entry with the given ID, then returns the newly-deleted object.
+/// Deleting an entry that was on my blog redeploys it.
 ///
 /// This endpoint must be called with a bearer token header:
 ///
@@ -185,7 +655,7 @@ pub(crate) async fn get_guestbook_entry(
 /// ```
 #[utoipa::path(
     responses(
-        (status=200, description = "Success response", body = inline(Entry))
+        (status=200, description = "Success response", body = inline(AdminEntry))
     ),
     tag = "Guestbook"
 )]
@@ -195,34 +665,497 @@ pub(crate) async fn delete_guestbook_entry(
     state: web::Data<crate::AppState>,
 ) -> Result<HttpResponse, ApiError> {
     let entry_id = path.into_inner();
-    let mut entry = get_single_entry(&state.dynamodb, &entry_id).await?;
-    entry.deleted_at = Some(chrono::Utc::now());
-    put_guestbook_entry(&state.dynamodb, &entry).await?;
-    entry.push_ser_option("serialize_deleted_at");
-    entry.push_ser_option("serialize_qa");
-
-    Ok(HttpResponse::Ok().json(&entry))
-}
-
-const HTMX_GUESTBOOK_LIST_TEMPLATE: &'static str = r#"
-{% for entry in entries %}
-<li class="guestbook-entry" data-entry="{{entry.id}}">
-    <div class="guestbook-metadata" hx-disable>
-        <span class="name">{{entry.name}}</span>
-        <div>
-        <span class="timestamp metadata">
-            <span>Just now</span>
-        </span>
-        {% if entry.url %}
-        <span class="url">
-            <a href="{{entry.url}}">{{entry.url}}</a>
-        </span>
-        {% endif %}
-        </div>
-    </div>
-    <div class="message" hx-disable>
-        <p>{{entry.message}}</p>
-    </div>
-</li>
-{% endfor %}
-"#;
+    let entry = GuestbookAction::Delete
+        .perform(state.guestbook.as_ref(), &entry_id)
+        .await?;
+
+    let _ = send_slack(
+        &format!("Deleted guestbook entry {} from {}", entry_id, entry.name),
+        SlackChannel::JilGuestbook,
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(AdminEntry::from(entry)))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub(crate) struct DeleteGuestbookQueryParameters {
+    /// Remove the entry from the database entirely, instead of marking it deleted.
+    #[serde(default)]
+    pub hard: bool,
+}
+
+/// Delete a Guestbook Entry (REST)
+///
+/// Deletes the guestbook entry with the given ID, then returns the newly-deleted object.
+/// Deleting an entry that was on my blog redeploys it.
+///
+/// By default this marks the entry as deleted, just like `POST /guestbook/{id}/delete`.
+/// Passing `hard=true` removes the entry from the database for good (for example, to
+/// honour a request to erase someone's data). This can't be undone.
+///
+/// This endpoint must be called with a bearer token header:
+///
+/// ```
+/// Authorization: Bearer admin
+/// ```
+#[utoipa::path(
+    params(DeleteGuestbookQueryParameters),
+    responses(
+        (status=200, description = "Success response", body = inline(AdminEntry))
+    ),
+    tag = "Guestbook"
+)]
+#[delete("/guestbook/{id}")]
+pub(crate) async fn delete_guestbook_entry_rest(
+    path: web::Path<uuid::Uuid>,
+    query: web::Query<DeleteGuestbookQueryParameters>,
+    state: web::Data<crate::AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let entry_id = path.into_inner();
+
+    let entry = if query.hard {
+        let entry = hard_delete_entry(state.guestbook.as_ref(), &entry_id).await?;
+        // Leave the entry's contents out of Slack, since they're meant to be gone.
+        let _ = send_slack(
+            &format!("Permanently deleted guestbook entry {entry_id}"),
+            SlackChannel::JilGuestbook,
+        )
+        .await;
+        entry
+    } else {
+        let entry = GuestbookAction::Delete
+            .perform(state.guestbook.as_ref(), &entry_id)
+            .await?;
+        let _ = send_slack(
+            &format!("Deleted guestbook entry {} from {}", entry_id, entry.name),
+            SlackChannel::JilGuestbook,
+        )
+        .await;
+        entry
+    };
+
+    Ok(HttpResponse::Ok().json(AdminEntry::from(entry)))
+}
+
+/// Restore a Guestbook Entry
+///
+/// Undoes the deletion of the guestbook entry with the given ID, then returns the
+/// newly-restored object. Restoring an entry that belongs on my blog redeploys it.
+///
+/// Entries deleted with `hard=true` are gone for good and can't be restored.
+///
+/// This endpoint must be called with a bearer token header:
+///
+/// ```
+/// Authorization: Bearer admin
+/// ```
+#[utoipa::path(
+    responses(
+        (status=200, description = "Success response", body = inline(AdminEntry))
+    ),
+    tag = "Guestbook"
+)]
+#[post("/guestbook/{id}/restore")]
+pub(crate) async fn restore_guestbook_entry(
+    path: web::Path<uuid::Uuid>,
+    state: web::Data<crate::AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let entry_id = path.into_inner();
+    let entry = GuestbookAction::Restore
+        .perform(state.guestbook.as_ref(), &entry_id)
+        .await?;
+
+    let _ = send_slack(
+        &format!("Restored guestbook entry {} from {}", entry_id, entry.name),
+        SlackChannel::JilGuestbook,
+    )
+    .await;
+
+    Ok(HttpResponse::Ok().json(AdminEntry::from(entry)))
+}
+
+/// Approve a Guestbook Entry
+///
+/// Publishes the guestbook entry with the given ID, then returns the newly-approved
+/// object. Approving an entry that wasn't submitted with `qa: true` redeploys my blog.
+///
+/// This endpoint must be called with a bearer token header:
+///
+/// ```
+/// Authorization: Bearer admin
+/// ```
+#[utoipa::path(
+    responses(
+        (status=200, description = "Success response", body = inline(AdminEntry))
+    ),
+    tag = "Guestbook"
+)]
+#[post("/guestbook/{id}/approve")]
+pub(crate) async fn approve_guestbook_entry(
+    path: web::Path<uuid::Uuid>,
+    state: web::Data<crate::AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let entry_id = path.into_inner();
+    let entry = GuestbookAction::Approve
+        .perform(state.guestbook.as_ref(), &entry_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(AdminEntry::from(entry)))
+}
+
+/// Reject a Guestbook Entry
+///
+/// Keeps the guestbook entry with the given ID from being published, then returns the
+/// newly-rejected object. Rejecting an entry that was already on my blog redeploys it.
+///
+/// This endpoint must be called with a bearer token header:
+///
+/// ```
+/// Authorization: Bearer admin
+/// ```
+#[utoipa::path(
+    responses(
+        (status=200, description = "Success response", body = inline(AdminEntry))
+    ),
+    tag = "Guestbook"
+)]
+#[post("/guestbook/{id}/reject")]
+pub(crate) async fn reject_guestbook_entry(
+    path: web::Path<uuid::Uuid>,
+    state: web::Data<crate::AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let entry_id = path.into_inner();
+    let entry = GuestbookAction::Reject
+        .perform(state.guestbook.as_ref(), &entry_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(AdminEntry::from(entry)))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct ReactionForm {
+    /// One of 👍, ❤️, 😂, 👋, or ✨.
+    #[schema(inline)]
+    pub emoji: Reaction,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ReactionResponse {
+    #[schema(inline)]
+    emoji: Reaction,
+
+    /// How many times the entry has gotten this reaction, including this one.
+    #[schema(example = "3")]
+    count: u32,
+}
+
+/// React to a Guestbook Entry
+///
+/// Adds an emoji reaction to the guestbook entry with the given ID, then returns the
+/// entry's new count of that reaction. Only listed entries can be reacted to.
+///
+/// Each visitor can leave each reaction on an entry once a day; reacting again gets a
+/// 409. Reactions also count against the API's global rate limit.
+#[utoipa::path(
+    request_body(content = inline(ReactionForm)),
+    responses(
+        (status=200, description = "Success response", body = inline(ReactionResponse)),
+        (status=400, description = "The visitor's IP address couldn't be determined"),
+        (status=409, description = "The visitor already left this reaction"),
+    ),
+    tag = "Guestbook"
+)]
+#[post("/guestbook/{id}/reactions")]
+pub(crate) async fn post_guestbook_reaction(
+    req: HttpRequest,
+    path: web::Path<uuid::Uuid>,
+    data: Either<web::Form<ReactionForm>, web::Json<ReactionForm>>,
+    state: web::Data<crate::AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let entry_id = path.into_inner();
+    let reaction = data.into_inner().emoji;
+
+    let entry = state.guestbook.get_entry(&entry_id).await?;
+    if !entry.is_published() {
+        return Err(
+            anyhow::Error::new(NotFound(format!("No entry found with ID {entry_id}"))).into(),
+        );
+    }
+
+    // Visitors are told apart by IP address, so without one there's nothing to dedupe on.
+    let ip = client_ip(&req)
+        .ok_or_else(|| ApiError::bad_request("Couldn't tell where this reaction came from."))?
+        .to_string();
+
+    if !state
+        .guestbook_reactions
+        .try_record(&ip, entry_id, reaction)
+    {
+        return Err(ApiError::conflict("You've already left this reaction."));
+    }
+
+    if state.rate_limiter.check().is_err() {
+        state.guestbook_reactions.forget(&ip, entry_id, reaction);
+        return Err(ApiError::rate_limit_error());
+    }
+
+    let count = match state.guestbook.add_reaction(&entry_id, reaction).await {
+        Ok(count) => count,
+        Err(err) => {
+            state.guestbook_reactions.forget(&ip, entry_id, reaction);
+            return Err(err.into());
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(ReactionResponse {
+        emoji: reaction,
+        count,
+    }))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct ReplyForm {
+    /// Limited to 1200 characters, counting each emoji as one.
+    #[schema(example = "Thanks for stopping by!", max_length = 1200)]
+    pub text: String,
+}
+
+/// Reply to a Guestbook Entry
+///
+/// Adds my reply to the guestbook entry with the given ID, replacing any earlier
+/// reply, then returns the updated object. Replying to an entry that's on my blog
+/// redeploys it.
+///
+/// This endpoint must be called with a bearer token header:
+///
+/// ```
+/// Authorization: Bearer admin
+/// ```
+#[utoipa::path(
+    request_body(content = inline(ReplyForm)),
+    responses(
+        (status=200, description = "Success response", body = inline(AdminEntry))
+    ),
+    tag = "Guestbook"
+)]
+#[post("/guestbook/{id}/reply")]
+pub(crate) async fn reply_guestbook_entry(
+    path: web::Path<uuid::Uuid>,
+    data: Either<web::Form<ReplyForm>, web::Json<ReplyForm>>,
+    state: web::Data<crate::AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let entry_id = path.into_inner();
+    let text = validate_reply(&data.into_inner().text)?;
+
+    let entry = reply_to_entry(state.guestbook.as_ref(), &entry_id, &text).await?;
+
+    Ok(HttpResponse::Ok().json(AdminEntry::from(entry)))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub(crate) struct GetAdminGuestbookQueryParameters {
+    /// One of `live`, `qa`, `deleted`, `pending`, or `rejected`. Leave it out to list
+    /// every entry.
+    #[param(inline)]
+    pub status: Option<StatusFilter>,
+
+    /// Only list entries created at or after this time (RFC 3339).
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Only list entries created before this time (RFC 3339).
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Only list entries whose name or message contains this text, ignoring case.
+    pub q: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct GetAdminGuestbookResponse {
+    items: Vec<AdminEntry>,
+
+    #[schema(example = "10")]
+    count: usize,
+}
+
+/// List Guestbook Entries for Moderation
+///
+/// Lists guestbook entries newest first, including deleted, QA, pending, and rejected
+/// entries, and including every entry's `deleted_at`, `qa`, `approved_at`, and `email`
+/// fields.
+///
+/// This endpoint must be called with a bearer token header:
+///
+/// ```
+/// Authorization: Bearer admin
+/// ```
+#[utoipa::path(
+    params(GetAdminGuestbookQueryParameters),
+    responses(
+        (status=200, description = "Success response", body = inline(GetAdminGuestbookResponse))
+    ),
+    tag = "Guestbook"
+)]
+#[get("/admin/guestbook")]
+pub(crate) async fn get_admin_guestbook(
+    query: web::Query<GetAdminGuestbookQueryParameters>,
+    state: web::Data<crate::AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let query = query.into_inner();
+
+    let entries = get_filtered_entries(
+        state.guestbook.as_ref(),
+        &AdminFilter {
+            status: query.status,
+            from: query.from,
+            to: query.to,
+            search: query.q.filter(|q| !q.is_empty()),
+        },
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(GetAdminGuestbookResponse {
+        count: entries.len(),
+        items: entries.into_iter().map(AdminEntry::from).collect(),
+    }))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub(crate) struct ExportGuestbookQueryParameters {
+    /// `jsonl` (the default) or `csv`.
+    #[param(inline)]
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Export the Guestbook
+///
+/// Downloads every guestbook entry, including deleted, QA, pending, and rejected
+/// entries, and including every entry's email.
+///
+/// The `jsonl` export has one stored entry per line, exactly as it's kept in the
+/// database, and can be restored with `POST /admin/guestbook/import`. The `csv`
+/// export is for reading in a spreadsheet, so cells that start like a formula (with
+/// `=`, `+`, `-`, or `@`) get a `'` in front.
+///
+/// This endpoint must be called with a bearer token header:
+///
+/// ```
+/// Authorization: Bearer admin
+/// ```
+#[utoipa::path(
+    params(ExportGuestbookQueryParameters),
+    responses(
+        (status=200, description = "Success response", content_type = "application/jsonl", body = String)
+    ),
+    tag = "Guestbook"
+)]
+#[get("/admin/guestbook/export")]
+pub(crate) async fn export_guestbook(
+    query: web::Query<ExportGuestbookQueryParameters>,
+    state: web::Data<crate::AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let format = query.format;
+    let mut entries = state.guestbook.scan_entries().await?;
+    entries.sort_by_key(|entry| (entry.created_at, entry.id));
+
+    let file_name = format!(
+        "guestbook-{}.{}",
+        chrono::Utc::now().format("%Y-%m-%d"),
+        format.extension()
+    );
+
+    let lines = format
+        .header()
+        .into_iter()
+        .map(Ok)
+        .chain(entries.into_iter().map(move |entry| format.line(entry)))
+        .map(|line| line.map(web::Bytes::from));
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(file_name)],
+        })
+        .streaming(futures::stream::iter(lines)))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub(crate) struct ImportGuestbookQueryParameters {
+    /// Report what the import would change without changing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Import Guestbook Entries
+///
+/// Reads entries from a `jsonl` export, one per line, and writes each one over the
+/// stored entry with the same ID, creating it if it doesn't exist. Stored entries
+/// keep their reactions, and stored entries that aren't in the import are left alone.
+/// Returns the IDs of the entries that were created and updated.
+///
+/// If any line isn't an entry, nothing is imported, and the bad lines are listed
+/// under `fields`. Importing doesn't redeploy my blog.
+///
+/// This endpoint must be called with a bearer token header:
+///
+/// ```
+/// Authorization: Bearer admin
+/// ```
+#[utoipa::path(
+    params(ImportGuestbookQueryParameters),
+    request_body(content = String, content_type = "application/jsonl"),
+    responses(
+        (status=200, description = "Success response", body = inline(ImportReport))
+    ),
+    tag = "Guestbook"
+)]
+#[post("/admin/guestbook/import")]
+pub(crate) async fn import_guestbook(
+    query: web::Query<ImportGuestbookQueryParameters>,
+    body: String,
+    state: web::Data<crate::AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let entries = parse_jsonl(&body)?;
+    let report = import_entries(state.guestbook.as_ref(), entries, query.dry_run).await?;
+
+    if !query.dry_run {
+        let _ = send_slack(
+            &format!(
+                "Imported guestbook entries: {} created, {} updated",
+                report.created.len(),
+                report.updated.len()
+            ),
+            SlackChannel::JilGuestbook,
+        )
+        .await;
+    }
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Recount Guestbook Entries
+///
+/// Counts every guestbook entry from scratch and returns the counts. Listings report
+/// totals that are kept up to date as entries change, rather than counted on every
+/// request; recounting fixes them if they ever drift.
+///
+/// This endpoint must be called with a bearer token header:
+///
+/// ```
+/// Authorization: Bearer admin
+/// ```
+#[utoipa::path(
+    responses(
+        (status=200, description = "Success response", body = inline(EntryCounts))
+    ),
+    tag = "Guestbook"
+)]
+#[post("/admin/guestbook/recount")]
+pub(crate) async fn recount_guestbook(
+    state: web::Data<crate::AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let counts = state.guestbook.recount_entries().await?;
+    Ok(HttpResponse::Ok().json(counts))
+}
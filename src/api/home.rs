@@ -22,13 +22,7 @@ async fn get_ip_attribution_string_from_request(
         .realip_remote_addr()
         .map(|ip| ip.to_string())?;
 
-    let ip_info = state
-        .ipinfo_cached_client
-        .lock()
-        .await
-        .get_ip_info(&ip)
-        .await
-        .ok();
+    let ip_info = state.ipinfo_cached_client.get_ip_info(&ip).await.ok();
 
     Some(match ip_info {
         None => format!("{} (No info)", ip),
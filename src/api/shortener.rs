@@ -1,28 +1,78 @@
-use actix_web::{get, post, web, Either, HttpResponse};
+use actix_web::{
+    get,
+    guard::GuardContext,
+    http::{header, StatusCode},
+    post, web, Either, HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use minijinja::context;
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
 use crate::{
-    error::ApiError,
+    client_ip::client_ip,
+    error::{ApiError, Conflict, NotFound, ValidationErrors},
     shortener::{
-        entry::Entry,
-        queries::{get_shortlink_entry, list_shortlink_entries, put_shortlink_entry},
+        clicks::{is_public_ip, referrer_host, ClickEvent, UserAgentClass},
+        entry::{normalize_shortname, Entry},
+        generate::{generate_shortname, generated_shortname_length, MAX_GENERATE_ATTEMPTS},
+        queries::list_shortlink_entries,
+        stats::{summarize, ClickStats, MAX_STATS_DAYS},
+        validation::{shortlink_host, validate_longurl},
     },
     slack::{channel::SlackChannel, send_slack_message, SlackApiRequest},
 };
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub(crate) struct CreateEntryForm {
-    pub shortname: String,
+    /// Up to 64 letters, numbers, dashes, and underscores, saved in lowercase. Leave it
+    /// out to get a random one.
+    #[schema(example = "stork")]
+    pub shortname: Option<String>,
+
+    /// An absolute http(s) URL.
+    #[schema(example = "https://stork-search.net")]
     pub longurl: String,
+
+    /// Reuse the shortname of a deleted or expired entry, replacing it. Its click count
+    /// starts over from zero.
+    #[serde(default)]
+    pub reclaim: bool,
+
+    /// When the shortlink stops working, which must be in the future.
+    #[schema(example = "2026-12-31T23:59:59Z")]
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// When the shortlink starts working. Until then, it resolves as if it didn't exist.
+    #[schema(example = "2026-12-01T09:00:00Z")]
+    pub not_before: Option<DateTime<Utc>>,
 }
 
 /// Create a Shortener Entry
 ///
-/// Creates an entry to be added to my personal link shortener.
+/// Creates an entry to be added to my personal link shortener. The new shortlink
+/// resolves at `GET /s/{shortname}`.
+///
+/// Shortnames that the API uses itself, like `admin` and `s`, are reserved. Long URLs
+/// must be http(s) URLs, and can't point at another shortlink. Invalid entries get a 400
+/// response whose body has a `reason` of `invalid_fields` and a `fields` list of every
+/// problem found.
+///
+/// Shortnames aren't case-sensitive: they're saved in lowercase, and `Stork` resolves
+/// the same shortlink as `stork`. A shortname that's already taken gets a 409 response
+/// with a `reason` of `shortname_taken`. Checking and saving the shortname
+/// happen in one write, so two requests for the same shortname can't both succeed.
+///
+/// Deleted entries keep their shortnames, so their click counts aren't lost. Using one
+/// gets a 409 response with a `reason` of `shortname_deleted`, unless `reclaim` is set,
+/// in which case the deleted entry is replaced.
+///
+/// Shortlinks can be limited to a window of time with `not_before` and `expires_at`.
+/// Expired entries are deleted from the database a little while after they expire, and
+/// their shortnames can be reclaimed like deleted ones.
 ///
-/// This API does not resolve the shortlinks to long URLs; it only holds a
-/// key-value store for the mapping between the two.
+/// Without a shortname, the entry gets a random one of lowercase letters and digits (6
+/// characters long, unless configured otherwise), which is returned in the response.
 ///
 /// This endpoint must be called with a bearer token header:
 ///
@@ -32,7 +82,9 @@ pub(crate) struct CreateEntryForm {
 #[utoipa::path(
     request_body = inline(CreateEntryForm),
     responses(
-        (status=200, description = "Success response", body=inline(Entry))
+        (status=200, description = "Success response", body=inline(Entry)),
+        (status=400, description = "The shortname or long URL is invalid"),
+        (status=409, description = "The shortname is taken, or belongs to a deleted entry"),
     ),
     tag = "Link Shortener"
 )]
@@ -42,20 +94,75 @@ pub(crate) async fn create_entry(
     payload: Either<web::Json<CreateEntryForm>, web::Form<CreateEntryForm>>,
 ) -> Result<HttpResponse, ApiError> {
     let payload = payload.into_inner();
-    let get_result = get_shortlink_entry(&state.dynamodb, &payload.shortname).await;
-    if let Ok(entry) = get_result {
-        if entry.deleted_at.is_none() {
-            return Err(ApiError::bad_request("Shortname already exists"));
+    let reclaim = payload.reclaim;
+    let generated = payload
+        .shortname
+        .as_deref()
+        .map_or(true, |shortname| shortname.is_empty());
+    let shortener_entry = Entry::try_from(payload)?;
+
+    if generated {
+        let shortener_entry = insert_generated_entry(&state, shortener_entry).await?;
+        return Ok(HttpResponse::Ok().json(&shortener_entry));
+    }
+
+    let inserted = if reclaim {
+        state.shortener.reclaim_entry(&shortener_entry).await
+    } else {
+        state.shortener.insert_entry(&shortener_entry).await
+    };
+
+    match inserted {
+        Ok(()) => Ok(HttpResponse::Ok().json(&shortener_entry)),
+        Err(err) if err.is::<Conflict>() => {
+            Err(shortname_conflict(&state, &shortener_entry.shortname).await)
         }
+        Err(err) => Err(err.into()),
     }
-    let shortener_entry = Entry::try_from(payload)?;
-    put_shortlink_entry(&state.dynamodb, &shortener_entry).await?;
-    Ok(HttpResponse::Ok().json(&shortener_entry))
+}
+
+/// The 409 for a shortname that couldn't be saved, which says whether it can be
+/// reclaimed.
+async fn shortname_conflict(state: &crate::AppState, shortname: &str) -> ApiError {
+    match state.shortener.get_entry(shortname).await {
+        Ok(existing) if existing.deleted_at.is_some() || existing.has_expired(Utc::now()) => {
+            ApiError::conflict(&format!(
+                "Shortname {shortname} belongs to a deleted or expired entry. Set reclaim to replace it."
+            ))
+            .with_reason("shortname_deleted")
+        }
+        _ => ApiError::conflict(&format!("Shortname {shortname} is already taken"))
+            .with_reason("shortname_taken"),
+    }
+}
+
+/// Saves an entry with a generated shortname, generating new ones until one isn't
+/// taken.
+async fn insert_generated_entry(
+    state: &crate::AppState,
+    mut entry: Entry,
+) -> Result<Entry, ApiError> {
+    let length = generated_shortname_length();
+    for _ in 0..MAX_GENERATE_ATTEMPTS {
+        match state.shortener.insert_entry(&entry).await {
+            Ok(()) => return Ok(entry),
+            Err(err) if err.is::<Conflict>() => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        entry.shortname = generate_shortname(length);
+    }
+
+    Err(ApiError::internal_server_error(
+        "Could not generate an unused shortname",
+    ))
 }
 
 /// List Shortener Entries
 ///
-/// Lists all key/value pairs that are saved as link shortener entries.
+/// Lists all key/value pairs that are saved as link shortener entries. Only shortlinks
+/// that work right now are listed: deleted, expired, and not-yet-started ones are left
+/// out.
 #[utoipa::path(
     responses(
         (status=200, description = "Success response")
@@ -66,15 +173,333 @@ pub(crate) async fn create_entry(
 pub(crate) async fn list_entries(
     state: web::Data<crate::AppState>,
 ) -> Result<HttpResponse, ApiError> {
-    let (_, entries) = list_shortlink_entries(&state.dynamodb).await?;
+    let (_, entries) = list_shortlink_entries(state.shortener.as_ref()).await?;
     Ok(HttpResponse::Ok().json(serde_json::json!({ "items": entries })))
 }
 
-// Get single entry
-// #[get("/shortener/entries/{id}")]
+/// Follow a Shortlink
+///
+/// Redirects to the long URL saved for the given shortname, and counts the click.
+///
+/// The redirect is a 302 rather than a 301, since browsers cache 301s: a cached
+/// redirect wouldn't be counted, and would outlive changes to the entry.
+///
+/// Unknown shortnames, and those whose `not_before` hasn't passed yet, get a 404 page.
+/// Deleted and expired ones get a 410 page.
+///
+/// Requests for the shortlink domain (`jil.im`, or `SHORTLINK_HOST` when it's set) are
+/// resolved at the root too, so `https://jil.im/{shortname}` works the same way.
+#[utoipa::path(
+    responses(
+        (status=302, description = "Redirect to the entry's long URL"),
+        (status=404, description = "No entry has this shortname, or it hasn't started working yet", content_type = "text/html", body = String),
+        (status=410, description = "The entry was deleted, or has expired", content_type = "text/html", body = String),
+    ),
+    tag = "Link Shortener"
+)]
+#[get("/s/{shortname}")]
+pub(crate) async fn resolve_shortlink(
+    req: HttpRequest,
+    state: web::Data<crate::AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    resolve(&req, &state, &path.into_inner()).await
+}
+
+/// `GET /s/{shortname}` for requests to the shortlink domain, which don't need the
+/// `/s` prefix.
+#[get("/{shortname}", guard = "is_shortlink_host")]
+pub(crate) async fn resolve_shortlink_host(
+    req: HttpRequest,
+    state: web::Data<crate::AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    resolve(&req, &state, &path.into_inner()).await
+}
+
+fn is_shortlink_host(ctx: &GuardContext) -> bool {
+    let shortlink_host = shortlink_host();
+
+    let host = ctx
+        .head()
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .or_else(|| ctx.head().uri.host());
+
+    // Ignore any port.
+    host.and_then(|host| host.split(':').next())
+        .map_or(false, |host| host.eq_ignore_ascii_case(&shortlink_host))
+}
+
+async fn resolve(
+    req: &HttpRequest,
+    state: &web::Data<crate::AppState>,
+    shortname: &str,
+) -> Result<HttpResponse, ApiError> {
+    let entry = match find_entry(state, shortname).await {
+        Ok(entry) => entry,
+        Err(err) if err.is::<NotFound>() => {
+            return missing_page(state, shortname, StatusCode::NOT_FOUND)
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let now = Utc::now();
+    if entry.deleted_at.is_some() || entry.has_expired(now) {
+        return missing_page(state, shortname, StatusCode::GONE);
+    }
+    if entry.is_scheduled(now) {
+        return missing_page(state, shortname, StatusCode::NOT_FOUND);
+    }
 
-// Update single entry
-// #[post("/shortener/entries/{id}")]
+    // A lost click isn't worth failing the redirect over.
+    let shortname = &entry.shortname;
+    if let Err(err) = state.shortener.record_click(shortname).await {
+        log::warn!("Could not count a click on shortlink {shortname}: {err}");
+    }
+    log_click(req, state, shortname);
+
+    Ok(HttpResponse::Found()
+        .insert_header((header::LOCATION, entry.longurl))
+        .finish())
+}
+
+/// Saves the click to the click log in the background, so that looking up where it
+/// came from doesn't hold up the redirect.
+fn log_click(req: &HttpRequest, state: &web::Data<crate::AppState>, shortname: &str) {
+    let header_value = |name: header::HeaderName| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+
+    let mut event = ClickEvent {
+        shortname: shortname.to_string(),
+        clicked_at: Utc::now(),
+        referrer: referrer_host(header_value(header::REFERER)),
+        user_agent: UserAgentClass::from_user_agent(header_value(header::USER_AGENT)),
+        country: None,
+    };
+    let ip = client_ip(req).filter(is_public_ip);
+    let state = state.clone();
+
+    actix_web::rt::spawn(async move {
+        if let Some(ip) = ip {
+            let ip_info = state
+                .ipinfo_cached_client
+                .get_ip_info(&ip.to_string())
+                .await;
+            match ip_info {
+                Ok(ip_info) => event.country = Some(ip_info.ip_info.country().to_string()),
+                Err(err) => log::warn!("Could not look up where {ip} is: {err}"),
+            }
+        }
+
+        if let Err(err) = state.shortener.record_click_event(&event).await {
+            log::warn!(
+                "Could not log a click on shortlink {}: {err}",
+                event.shortname
+            );
+        }
+    });
+}
+
+fn missing_page(
+    state: &crate::AppState,
+    shortname: &str,
+    status: StatusCode,
+) -> Result<HttpResponse, ApiError> {
+    let body = state.templates.render(
+        "shortener/missing.html",
+        context! { shortname => shortname, gone => status == StatusCode::GONE },
+    )?;
+
+    Ok(HttpResponse::build(status)
+        .content_type(header::ContentType::html())
+        .body(body))
+}
+
+/// Get a Shortener Entry
+///
+/// Returns the entry with the given shortname. Deleted entries aren't returned.
+#[utoipa::path(
+    responses(
+        (status=200, description = "Success response", body=inline(Entry)),
+        (status=404, description = "No entry has this shortname"),
+    ),
+    tag = "Link Shortener"
+)]
+#[get("/shortener/entries/{id}")]
+pub(crate) async fn get_entry(
+    state: web::Data<crate::AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let entry = get_undeleted_entry(&state, &path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(&entry))
+}
+
+/// Returns the entry with the given shortname, in lowercase. Entries saved with
+/// uppercase letters before shortnames were lowercased are found under the shortname
+/// exactly as given, until the `2026_10_18_05` migration moves them.
+async fn find_entry(state: &crate::AppState, shortname: &str) -> anyhow::Result<Entry> {
+    let normalized = normalize_shortname(shortname);
+    match state.shortener.get_entry(&normalized).await {
+        Err(err) if err.is::<NotFound>() && normalized != shortname => {
+            state.shortener.get_entry(shortname).await
+        }
+        found => found,
+    }
+}
+
+async fn get_undeleted_entry(state: &crate::AppState, shortname: &str) -> Result<Entry, ApiError> {
+    let entry = find_entry(state, shortname).await?;
+    if entry.deleted_at.is_some() {
+        return Err(anyhow::Error::new(NotFound(format!(
+            "No entry found with shortname {shortname}"
+        )))
+        .into());
+    }
+    Ok(entry)
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct UpdateEntryForm {
+    /// An absolute http(s) URL, which can't point at another shortlink.
+    #[schema(example = "https://stork-search.net")]
+    pub longurl: String,
+
+    /// The `version` of the entry this change was made to. If someone else has updated
+    /// the entry since, the update fails with a 409 instead of overwriting their change.
+    #[schema(example = 3)]
+    pub version: Option<u32>,
+}
+
+/// Update a Shortener Entry
+///
+/// Points the entry with the given shortname at a new long URL, then returns the
+/// updated entry. Its `created_at` and `clicks` are kept.
+///
+/// Every update increments the entry's `version`. Pass the version you last saw to make
+/// sure you're not overwriting someone else's update; either way, two updates racing
+/// each other can't both win, and the loser gets a 409.
+///
+/// This endpoint must be called with a bearer token header:
+///
+/// ```
+/// Authorization: Bearer admin
+/// ```
+#[utoipa::path(
+    request_body = inline(UpdateEntryForm),
+    responses(
+        (status=200, description = "Success response", body=inline(Entry)),
+        (status=404, description = "No entry has this shortname"),
+        (status=409, description = "The entry was updated by someone else"),
+    ),
+    tag = "Link Shortener"
+)]
+#[post("/shortener/entries/{id}")]
+pub(crate) async fn update_entry(
+    state: web::Data<crate::AppState>,
+    path: web::Path<String>,
+    payload: Either<web::Json<UpdateEntryForm>, web::Form<UpdateEntryForm>>,
+) -> Result<HttpResponse, ApiError> {
+    let payload = payload.into_inner();
+    let mut entry = get_undeleted_entry(&state, &path.into_inner()).await?;
+
+    let mut errors = ValidationErrors::default();
+    validate_longurl(&mut errors, &payload.longurl);
+    if !errors.0.is_empty() {
+        return Err(errors.into());
+    }
+
+    let expected_version = payload.version.unwrap_or(entry.version);
+    if expected_version != entry.version {
+        return Err(ApiError::conflict(
+            "This entry was updated by someone else. Fetch it again and retry.",
+        ));
+    }
+
+    entry.longurl = payload.longurl;
+    entry.updated_at = Some(chrono::Utc::now());
+    entry.version = expected_version + 1;
+    state
+        .shortener
+        .update_entry(&entry, expected_version)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(&entry))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub(crate) struct GetEntryStatsQueryParameters {
+    /// The first day to include, like `2026-09-19`. Defaults to 29 days before `to`.
+    #[param(value_type = Option<String>)]
+    pub from: Option<NaiveDate>,
+
+    /// The last day to include, like `2026-10-18`. Defaults to today.
+    #[param(value_type = Option<String>)]
+    pub to: Option<NaiveDate>,
+}
+
+/// Get a Shortener Entry's Statistics
+///
+/// Returns the clicks on the entry with the given shortname between two dates
+/// (inclusive, in UTC): how many there were each day, along with where they came from
+/// and what kind of clients made them. Ranges can be up to 366 days long, and default
+/// to the last 30 days.
+///
+/// Clicks from before the click log existed only count toward the entry's `clicks`, and
+/// clicks on an earlier entry with the same shortname aren't counted at all.
+///
+/// This endpoint must be called with a bearer token header:
+///
+/// ```
+/// Authorization: Bearer admin
+/// ```
+#[utoipa::path(
+    params(GetEntryStatsQueryParameters),
+    responses(
+        (status=200, description = "Success response", body=inline(ClickStats)),
+        (status=400, description = "The date range is invalid"),
+        (status=404, description = "No entry has this shortname"),
+    ),
+    tag = "Link Shortener"
+)]
+#[get("/shortener/entries/{id}/stats")]
+pub(crate) async fn get_entry_stats(
+    state: web::Data<crate::AppState>,
+    path: web::Path<String>,
+    query: web::Query<GetEntryStatsQueryParameters>,
+) -> Result<HttpResponse, ApiError> {
+    let entry = find_entry(&state, &path.into_inner()).await?;
+
+    let to = query.to.unwrap_or_else(|| Utc::now().naive_utc().date());
+    let from = query.from.unwrap_or(to - Duration::days(29));
+    if from > to {
+        return Err(ApiError::bad_request("from must not be after to."));
+    }
+    if (to - from).num_days() >= MAX_STATS_DAYS {
+        return Err(ApiError::bad_request(&format!(
+            "Stats can cover at most {MAX_STATS_DAYS} days."
+        )));
+    }
+
+    // Clicks are logged by shortname, so a reclaimed shortname's log also has the
+    // clicks on the entry it replaced, from before this entry was created.
+    let since = DateTime::from_utc(from.and_hms(0, 0, 0), Utc).max(entry.created_at);
+    let until = DateTime::from_utc((to + Duration::days(1)).and_hms(0, 0, 0), Utc);
+    let events = if since < until {
+        state
+            .shortener
+            .click_events(&entry.shortname, since, until)
+            .await?
+    } else {
+        vec![]
+    };
+
+    Ok(HttpResponse::Ok().json(summarize(&entry.shortname, &events, from, to)))
+}
 
 /// Delete a Shortener Entry
 ///
@@ -94,21 +519,36 @@ pub(crate) async fn delete_entry(
     state: web::Data<crate::AppState>,
     path: web::Path<String>,
 ) -> Result<HttpResponse, ApiError> {
-    let entry_id = path.into_inner();
-    let mut entry = get_shortlink_entry(&state.dynamodb, &entry_id).await?;
-    entry.deleted_at = Some(chrono::Utc::now());
-    put_shortlink_entry(&state.dynamodb, &entry).await?;
+    let entry_id = find_entry(&state, &path.into_inner()).await?.shortname;
+    let entry = state
+        .shortener
+        .delete_entry(&entry_id, chrono::Utc::now())
+        .await?;
     Ok(HttpResponse::Ok().json(&entry))
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct ClickCount {
+    #[schema(example = "stork")]
+    pub shortname: String,
+
+    #[schema(example = 42)]
+    pub clicks: u32,
+}
+
 /// Update Statistics
 ///
+/// Sets the click counts of existing shortlinks. Each item only needs a `shortname`
+/// and `clicks`; any other fields are ignored, so whole entries can be sent too. Fails
+/// with a 404, partway through, if a shortname doesn't exist.
+///
 /// This endpoint must be called with a bearer token header:
 ///
 /// ```
 /// Authorization: Bearer admin
 /// ```
 #[utoipa::path(
+    request_body(content = Vec<ClickCount>),
     responses(
         (status=200, description = "Success response")
     ),
@@ -116,30 +556,23 @@ pub(crate) async fn delete_entry(
 )]
 #[post("/shortener/stats")]
 pub(crate) async fn update_stats(
-    payload: web::Json<serde_json::Value>,
+    payload: web::Json<Vec<ClickCount>>,
     state: web::Data<crate::AppState>,
 ) -> Result<HttpResponse, ApiError> {
-    if payload.is_array() {
-        for entry in payload.as_array().unwrap() {
-            let entry = serde_json::from_value(entry.clone()).map_err(|_| {
-                return ApiError::bad_request(
-                    format!("Could not parse entry: {:?}", entry).as_str(),
-                );
-            })?;
-            println!("Updating entry: {:?}", entry);
-            put_shortlink_entry(&state.dynamodb, &entry).await?;
-        }
-        let _ = send_slack_message(&SlackApiRequest {
-            channel: SlackChannel::General,
-            blocks: vec![],
-            text: format!(
-                "Updated {} shortlink entries",
-                payload.as_array().unwrap().len()
-            ),
-        })
-        .await;
-        Ok(HttpResponse::Ok().body("OK"))
-    } else {
-        Ok(HttpResponse::BadRequest().body("Payload must be an array"))
+    let counts = payload.into_inner();
+    for count in &counts {
+        state
+            .shortener
+            .set_clicks(&normalize_shortname(&count.shortname), count.clicks)
+            .await?;
     }
+
+    let _ = send_slack_message(&SlackApiRequest {
+        channel: SlackChannel::General,
+        blocks: vec![],
+        text: format!("Updated {} shortlink click counts", counts.len()),
+    })
+    .await;
+
+    Ok(HttpResponse::Ok().body("OK"))
 }
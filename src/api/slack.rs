@@ -2,8 +2,13 @@ use actix_web::{post, web, HttpRequest, HttpResponse};
 use serde_json::json;
 
 use crate::{
+    blog::deploy_blog,
     error::ApiError,
-    slack::{send_slack_message, SlackApiRequest},
+    guestbook::actions::GuestbookAction,
+    slack::{
+        interactions::{update_message, verify_signature, InteractionForm, InteractionPayload},
+        send_slack_message, SlackApiRequest,
+    },
 };
 
 /// Send a Slack message
@@ -67,3 +72,118 @@ pub(crate) async fn post_slack(
         Err(err) => Err(ApiError::internal_server_error(err.to_string().as_str())),
     }
 }
+
+/// Handle a Slack Interaction
+///
+/// The request URL for the Slack app's interactivity settings. Slack calls it when
+/// someone presses one of the buttons on a guestbook entry's message; the entry is
+/// approved, rejected, deleted, or restored accordingly. Once Slack has its response,
+/// the message is updated to show who did it.
+///
+/// Requests must carry a valid `X-Slack-Signature` header, made with the app's
+/// signing secret.
+#[utoipa::path(
+    responses(
+        (status=200, description = "Success response"),
+        (status=401, description = "The request wasn't signed by Slack"),
+    ),
+    tag="Generic"
+)]
+#[post("/slack/interactions")]
+pub(crate) async fn post_slack_interactions(
+    req: HttpRequest,
+    body: web::Bytes,
+    state: web::Data<crate::AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let signing_secret = std::env::var("SLACK_SIGNING_SECRET")
+        .map_err(|_| ApiError::internal_server_error("No Slack signing secret configured"))?;
+
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+    };
+
+    if !verify_signature(
+        &signing_secret,
+        header("X-Slack-Request-Timestamp"),
+        &body,
+        header("X-Slack-Signature"),
+        chrono::Utc::now().timestamp(),
+    ) {
+        return Err(ApiError::unauthorized("Invalid Slack signature"));
+    }
+
+    let payload = serde_urlencoded::from_bytes::<InteractionForm>(&body)
+        .ok()
+        .and_then(|form| serde_json::from_str::<InteractionPayload>(&form.payload).ok())
+        .ok_or_else(|| ApiError::bad_request("Could not parse interaction payload"))?;
+
+    if payload.kind != "block_actions" {
+        return Ok(HttpResponse::Ok().finish());
+    }
+
+    // Slack shows an error unless it hears back within 3 seconds, so redeploying the
+    // blog and updating the message happen after responding.
+    let mut redeploy = false;
+    let mut message_updates = vec![];
+
+    for action in &payload.actions {
+        let Some(guestbook_action) = GuestbookAction::from_action_id(&action.action_id) else {
+            continue;
+        };
+
+        let entry_id = action
+            .value
+            .as_deref()
+            .and_then(|value| uuid::Uuid::parse_str(value).ok())
+            .ok_or_else(|| ApiError::bad_request("Button value is not an entry ID"))?;
+
+        let (entry, entry_redeploy) = guestbook_action
+            .apply(state.guestbook.as_ref(), &entry_id)
+            .await?;
+        redeploy |= entry_redeploy;
+
+        if let (Some(response_url), Some(message)) = (&payload.response_url, &payload.message) {
+            // Swap the old buttons for a note about who pressed one, plus the
+            // buttons that make sense now.
+            let mut blocks: Vec<_> = message
+                .blocks
+                .iter()
+                .filter(|block| block.get("type") != Some(&json!("actions")))
+                .cloned()
+                .collect();
+
+            blocks.push(
+                json!({
+                    "type": "context",
+                    "elements": [
+                        {
+                            "type": "mrkdwn",
+                            "text": format!("*{}* by <@{}>", guestbook_action.past_tense(), payload.user.id)
+                        }
+                    ]
+                })
+                .as_object()
+                .unwrap()
+                .clone(),
+            );
+            blocks.push(entry.slack_actions_block());
+
+            message_updates.push((response_url.clone(), message.text.clone(), blocks));
+        }
+    }
+
+    actix_web::rt::spawn(async move {
+        if redeploy {
+            let _ = deploy_blog().await;
+        }
+
+        for (response_url, text, blocks) in message_updates {
+            let _ = update_message(&response_url, &text, &blocks).await;
+        }
+    });
+
+    Ok(HttpResponse::Ok().finish())
+}
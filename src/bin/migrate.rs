@@ -0,0 +1,12 @@
+use std::env;
+
+use jil_api::migrate;
+
+#[actix_web::main]
+async fn main() -> anyhow::Result<()> {
+    let name = env::args()
+        .nth(1)
+        .expect("Usage: migrate <name>, like `migrate 2026_10_18`");
+
+    migrate(&name).await
+}
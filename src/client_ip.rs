@@ -0,0 +1,55 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::OnceLock,
+};
+
+use actix_web::HttpRequest;
+
+/// The proxies whose `Forwarded` and `X-Forwarded-For` headers are believed, from
+/// `TRUSTED_PROXIES`: a comma-separated list of IP addresses.
+fn trusted_proxies() -> &'static [IpAddr] {
+    static PROXIES: OnceLock<Vec<IpAddr>> = OnceLock::new();
+    PROXIES.get_or_init(|| {
+        std::env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|proxy| proxy.trim().parse().ok())
+            .collect()
+    })
+}
+
+/// The IP address of the client that made the request.
+///
+/// Anyone can send forwarding headers, so they're only used when the request came
+/// straight from a trusted proxy. Otherwise, the client is whoever connected.
+pub(crate) fn client_ip(req: &HttpRequest) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    if !trusted_proxies().contains(&peer) {
+        return Some(peer);
+    }
+
+    let connection_info = req.connection_info();
+    let forwarded = connection_info.realip_remote_addr()?;
+    forwarded
+        .parse()
+        .ok()
+        .or_else(|| forwarded.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    #[test]
+    fn test_forwarded_headers_need_a_trusted_proxy() {
+        let req = TestRequest::default()
+            .peer_addr("203.0.113.9:4000".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "198.51.100.1"))
+            .to_http_request();
+        assert_eq!(client_ip(&req), Some(IpAddr::from([203, 0, 113, 9])));
+
+        assert_eq!(client_ip(&TestRequest::default().to_http_request()), None);
+    }
+}
@@ -5,11 +5,86 @@ use actix_web::{
     http::{header::ContentType, StatusCode},
     HttpResponse,
 };
+use serde::Serialize;
+
+/// Stores return this (wrapped in an `anyhow::Error`) when the item asked for
+/// doesn't exist, so that handlers respond with a 404.
+#[derive(Debug)]
+pub(crate) struct NotFound(pub String);
+
+impl Display for NotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+/// Stores return this (wrapped in an `anyhow::Error`) when a conditional write loses
+/// to another write, so that handlers respond with a 409.
+#[derive(Debug)]
+pub(crate) struct Conflict(pub String);
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Conflict {}
+
+/// A problem with one field of a submitted form.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct FieldError {
+    pub field: String,
+
+    /// A machine-readable description of the problem, like `required`.
+    pub code: &'static str,
+
+    pub message: String,
+}
+
+/// Every problem found with a submitted form, so that they can all be fixed at once.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ValidationErrors(pub Vec<FieldError>);
+
+impl ValidationErrors {
+    pub(crate) fn push(&mut self, field: &str, code: &'static str, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field: field.to_string(),
+            code,
+            message: message.into(),
+        });
+    }
+}
+
+impl Display for ValidationErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let messages = self
+            .0
+            .iter()
+            .map(|error| error.message.as_str())
+            .collect::<Vec<_>>();
+        f.write_str(&messages.join(" "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+impl From<ValidationErrors> for ApiError {
+    fn from(errors: ValidationErrors) -> Self {
+        ApiError::bad_request("Some fields are invalid.")
+            .with_reason("invalid_fields")
+            .with_field_errors(errors.0)
+    }
+}
 
 #[derive(Debug)]
 pub(crate) struct ApiError {
     message: String,
     status_code: StatusCode,
+    reason: Option<String>,
+    field_errors: Vec<FieldError>,
 }
 
 impl ApiError {
@@ -17,6 +92,35 @@ impl ApiError {
         Self {
             message: msg.to_string(),
             status_code: StatusCode::BAD_REQUEST,
+            reason: None,
+            field_errors: vec![],
+        }
+    }
+
+    pub(crate) fn unauthorized(msg: &str) -> Self {
+        Self {
+            message: msg.to_string(),
+            status_code: StatusCode::UNAUTHORIZED,
+            reason: None,
+            field_errors: vec![],
+        }
+    }
+
+    pub(crate) fn forbidden(msg: &str) -> Self {
+        Self {
+            message: msg.to_string(),
+            status_code: StatusCode::FORBIDDEN,
+            reason: None,
+            field_errors: vec![],
+        }
+    }
+
+    pub(crate) fn conflict(msg: &str) -> Self {
+        Self {
+            message: msg.to_string(),
+            status_code: StatusCode::CONFLICT,
+            reason: None,
+            field_errors: vec![],
         }
     }
 
@@ -24,6 +128,8 @@ impl ApiError {
         Self {
             message: msg.to_string(),
             status_code: StatusCode::INTERNAL_SERVER_ERROR,
+            reason: None,
+            field_errors: vec![],
         }
     }
 
@@ -31,30 +137,75 @@ impl ApiError {
         Self {
             message: "Too many requests made to this endpoint.".to_string(),
             status_code: StatusCode::TOO_MANY_REQUESTS,
+            reason: None,
+            field_errors: vec![],
         }
     }
+
+    /// Every message a person should see about the error: one per field error if
+    /// there are any, or else the error's message.
+    pub(crate) fn messages(&self) -> Vec<&str> {
+        if self.field_errors.is_empty() {
+            vec![&self.message]
+        } else {
+            self.field_errors
+                .iter()
+                .map(|error| error.message.as_str())
+                .collect()
+        }
+    }
+
+    /// Attaches a machine-readable `reason` to the error body, for clients that
+    /// want to react to specific failures.
+    pub(crate) fn with_reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_string());
+        self
+    }
+
+    /// Lists what's wrong with each field of a submitted form under `fields` in the
+    /// error body.
+    pub(crate) fn with_field_errors(mut self, field_errors: Vec<FieldError>) -> Self {
+        self.field_errors = field_errors;
+        self
+    }
 }
 
 impl From<anyhow::Error> for ApiError {
     fn from(err: anyhow::Error) -> Self {
+        let status_code = if err.is::<NotFound>() {
+            StatusCode::NOT_FOUND
+        } else if err.is::<Conflict>() {
+            StatusCode::CONFLICT
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+
         Self {
             message: err.to_string(),
-            status_code: StatusCode::INTERNAL_SERVER_ERROR,
+            status_code,
+            reason: None,
+            field_errors: vec![],
         }
     }
 }
 
 impl Display for ApiError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(
-            serde_json::json!({
-                "error": true,
-                "code": self.status_code.to_string(),
-                "message": self.message
-            })
-            .to_string()
-            .as_str(),
-        )
+        let mut body = serde_json::json!({
+            "error": true,
+            "code": self.status_code.to_string(),
+            "message": self.message
+        });
+
+        if let Some(reason) = &self.reason {
+            body["reason"] = serde_json::Value::from(reason.as_str());
+        }
+
+        if !self.field_errors.is_empty() {
+            body["fields"] = serde_json::json!(self.field_errors);
+        }
+
+        f.write_str(body.to_string().as_str())
     }
 }
 
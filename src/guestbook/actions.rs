@@ -0,0 +1,224 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::{
+    blog::deploy_blog,
+    guestbook::{
+        entry::{Entry, EntryStatus},
+        store::GuestbookStore,
+    },
+};
+
+/// Something an admin can do to a guestbook entry, either through the API or by
+/// pressing a button on the entry's Slack message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GuestbookAction {
+    Approve,
+    Reject,
+    Delete,
+    Restore,
+}
+
+impl GuestbookAction {
+    /// The `action_id` of the Slack button that performs this action.
+    pub(crate) fn action_id(&self) -> &'static str {
+        match self {
+            GuestbookAction::Approve => "guestbook_approve",
+            GuestbookAction::Reject => "guestbook_reject",
+            GuestbookAction::Delete => "guestbook_delete",
+            GuestbookAction::Restore => "guestbook_restore",
+        }
+    }
+
+    pub(crate) fn from_action_id(action_id: &str) -> Option<Self> {
+        [
+            GuestbookAction::Approve,
+            GuestbookAction::Reject,
+            GuestbookAction::Delete,
+            GuestbookAction::Restore,
+        ]
+        .into_iter()
+        .find(|action| action.action_id() == action_id)
+    }
+
+    pub(crate) fn past_tense(&self) -> &'static str {
+        match self {
+            GuestbookAction::Approve => "Approved",
+            GuestbookAction::Reject => "Rejected",
+            GuestbookAction::Delete => "Deleted",
+            GuestbookAction::Restore => "Restored",
+        }
+    }
+
+    /// Applies the action to the entry with the given ID and saves it, then returns
+    /// the updated entry. If the entry appeared on or disappeared from my blog as a
+    /// result, the blog is redeployed.
+    pub(crate) async fn perform(self, store: &dyn GuestbookStore, id: &Uuid) -> Result<Entry> {
+        let (entry, redeploy) = self.apply(store, id).await?;
+
+        if redeploy {
+            let _ = deploy_blog().await;
+        }
+
+        Ok(entry)
+    }
+
+    /// Like [`GuestbookAction::perform`], but leaves redeploying the blog to the caller:
+    /// the returned flag says whether it needs it.
+    pub(crate) async fn apply(
+        self,
+        store: &dyn GuestbookStore,
+        id: &Uuid,
+    ) -> Result<(Entry, bool)> {
+        let mut entry = store.get_entry(id).await?;
+        let was_published = entry.is_published();
+
+        match self {
+            GuestbookAction::Approve => {
+                entry.status = EntryStatus::Approved;
+                entry.approved_at = Some(chrono::Utc::now());
+            }
+            GuestbookAction::Reject => {
+                entry.status = EntryStatus::Rejected;
+                entry.approved_at = None;
+            }
+            GuestbookAction::Delete => entry.deleted_at = Some(chrono::Utc::now()),
+            GuestbookAction::Restore => entry.deleted_at = None,
+        }
+
+        store.put_entry(&entry).await?;
+
+        let redeploy = was_published != entry.is_published() && !entry.qa;
+        Ok((entry, redeploy))
+    }
+}
+
+/// Permanently removes the entry with the given ID, then returns what it was. If the
+/// entry was on my blog, the blog is redeployed.
+pub(crate) async fn hard_delete_entry(store: &dyn GuestbookStore, id: &Uuid) -> Result<Entry> {
+    let entry = store.get_entry(id).await?;
+    store.remove_entry(id).await?;
+
+    if entry.is_published() && !entry.qa {
+        let _ = deploy_blog().await;
+    }
+
+    Ok(entry)
+}
+
+/// Sets my reply to the entry with the given ID, replacing any earlier reply, then
+/// returns the updated entry. If the entry is on my blog, the blog is redeployed.
+pub(crate) async fn reply_to_entry(
+    store: &dyn GuestbookStore,
+    id: &Uuid,
+    text: &str,
+) -> Result<Entry> {
+    let mut entry = store.get_entry(id).await?;
+    entry.reply_text = Some(text.to_string());
+    entry.replied_at = Some(chrono::Utc::now());
+
+    store.put_entry(&entry).await?;
+
+    if entry.is_published() && !entry.qa {
+        let _ = deploy_blog().await;
+    }
+
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guestbook::{memory::InMemoryGuestbookStore, reactions::Reaction};
+
+    #[test]
+    fn test_action_ids_round_trip() {
+        for action in [
+            GuestbookAction::Approve,
+            GuestbookAction::Reject,
+            GuestbookAction::Delete,
+            GuestbookAction::Restore,
+        ] {
+            assert_eq!(
+                GuestbookAction::from_action_id(action.action_id()),
+                Some(action)
+            );
+        }
+        assert_eq!(GuestbookAction::from_action_id("click_me_123"), None);
+    }
+
+    #[tokio::test]
+    async fn test_delete_then_restore() {
+        let store = InMemoryGuestbookStore::default();
+        let entry = Entry {
+            qa: true,
+            ..Default::default()
+        };
+        store.put_entry(&entry).await.unwrap();
+
+        let deleted = GuestbookAction::Delete
+            .perform(&store, &entry.id)
+            .await
+            .unwrap();
+        assert!(deleted.deleted_at.is_some());
+
+        let restored = GuestbookAction::Restore
+            .perform(&store, &entry.id)
+            .await
+            .unwrap();
+        assert!(restored.is_published());
+    }
+
+    #[tokio::test]
+    async fn test_hard_delete() {
+        let store = InMemoryGuestbookStore::default();
+        let entry = Entry {
+            qa: true,
+            ..Default::default()
+        };
+        store.put_entry(&entry).await.unwrap();
+
+        hard_delete_entry(&store, &entry.id).await.unwrap();
+        assert!(store.get_entry(&entry.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reply() {
+        let store = InMemoryGuestbookStore::default();
+        let entry = Entry {
+            qa: true,
+            ..Default::default()
+        };
+        store.put_entry(&entry).await.unwrap();
+
+        reply_to_entry(&store, &entry.id, "Thanks!").await.unwrap();
+
+        let reply = store.get_entry(&entry.id).await.unwrap().reply().unwrap();
+        assert_eq!(reply.text, "Thanks!");
+    }
+
+    #[tokio::test]
+    async fn test_actions_keep_reactions_added_meanwhile() {
+        let store = InMemoryGuestbookStore::default();
+        let entry = Entry {
+            qa: true,
+            ..Default::default()
+        };
+        store.put_entry(&entry).await.unwrap();
+
+        let read_earlier = store.get_entry(&entry.id).await.unwrap();
+        store
+            .add_reaction(&entry.id, Reaction::Heart)
+            .await
+            .unwrap();
+        store.put_entry(&read_earlier).await.unwrap();
+        GuestbookAction::Delete
+            .perform(&store, &entry.id)
+            .await
+            .unwrap();
+
+        let stored = store.get_entry(&entry.id).await.unwrap();
+        assert!(stored.deleted_at.is_some());
+        assert_eq!(stored.reactions.get(Reaction::Heart.key()), Some(&1));
+    }
+}
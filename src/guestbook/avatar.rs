@@ -0,0 +1,119 @@
+use std::sync::OnceLock;
+
+use sha2::{Digest, Sha256};
+use url::form_urlencoded;
+use uuid::Uuid;
+
+use crate::guestbook::entry::Entry;
+
+const API_BASE_URL: &str = "https://api.jameslittle.me";
+
+/// What Gravatar shows for email addresses it doesn't know, from
+/// `GUESTBOOK_AVATAR_FALLBACK`. `identicon` (the default) means our own identicon;
+/// anything else is passed to Gravatar as its `d` parameter, e.g. `mp` or `retro`.
+fn gravatar_fallback() -> &'static str {
+    static FALLBACK: OnceLock<String> = OnceLock::new();
+    FALLBACK.get_or_init(|| {
+        std::env::var("GUESTBOOK_AVATAR_FALLBACK").unwrap_or_else(|_| "identicon".to_string())
+    })
+}
+
+/// The URL of the identicon served for the entry with the given ID.
+pub(crate) fn identicon_url(id: &Uuid) -> String {
+    format!("{API_BASE_URL}/guestbook/{}/avatar.svg", id.to_hyphenated())
+}
+
+/// The entry's avatar: its author's Gravatar when they left an email, or else a
+/// generated identicon. Only a hash of the email ever leaves the server.
+pub(crate) fn avatar_url(entry: &Entry) -> String {
+    let Some(email) = entry.email.as_deref() else {
+        return identicon_url(&entry.id);
+    };
+
+    let fallback = match gravatar_fallback() {
+        "identicon" => identicon_url(&entry.id),
+        fallback => fallback.to_string(),
+    };
+
+    format!(
+        "https://gravatar.com/avatar/{}?s=80&d={}",
+        gravatar_hash(email),
+        form_urlencoded::byte_serialize(fallback.as_bytes()).collect::<String>()
+    )
+}
+
+fn gravatar_hash(email: &str) -> String {
+    hex::encode(Sha256::digest(email.trim().to_lowercase().as_bytes()))
+}
+
+/// Draws a GitHub-style identicon for the given ID: a symmetric 5×5 grid of squares in
+/// a single colour, both picked from a hash of the ID.
+pub(crate) fn identicon_svg(id: &Uuid) -> String {
+    let hash = Sha256::digest(id.as_bytes());
+
+    let hue = u16::from_be_bytes([hash[0], hash[1]]) % 360;
+    let color = format!("hsl({hue}, 55%, 55%)");
+
+    let mut squares = String::new();
+    for row in 0..5 {
+        for column in 0..3 {
+            if hash[2 + row * 3 + column] % 2 == 0 {
+                continue;
+            }
+
+            // Mirror the left columns onto the right; the middle column is its own mirror.
+            let mirrored = 4 - column;
+            let columns = if mirrored == column {
+                vec![column]
+            } else {
+                vec![column, mirrored]
+            };
+            for x in columns {
+                squares.push_str(&format!(
+                    r#"<rect x="{}" y="{}" width="1" height="1"/>"#,
+                    x + 1,
+                    row + 1
+                ));
+            }
+        }
+    }
+
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 7 7" width="80" height="80" shape-rendering="crispEdges"><rect width="7" height="7" fill="#f0f0f0"/><g fill="{color}">{squares}</g></svg>"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_avatar_never_contains_the_email() {
+        let entry = Entry {
+            email: Some(" John@Example.com ".to_string()),
+            ..Default::default()
+        };
+
+        let url = avatar_url(&entry);
+        assert!(url.starts_with(&format!(
+            "https://gravatar.com/avatar/{}?",
+            gravatar_hash("john@example.com")
+        )));
+        assert!(!url.to_lowercase().contains("john"));
+        assert!(!url.to_lowercase().contains("example.com"));
+    }
+
+    #[test]
+    fn test_entries_without_email_get_an_identicon() {
+        let entry = Entry::default();
+        assert_eq!(avatar_url(&entry), identicon_url(&entry.id));
+    }
+
+    #[test]
+    fn test_identicons_are_deterministic() {
+        let id = Uuid::new_v4();
+        assert_eq!(identicon_svg(&id), identicon_svg(&id));
+        assert_ne!(identicon_svg(&id), identicon_svg(&Uuid::new_v4()));
+        assert!(identicon_svg(&id).starts_with("<svg"));
+    }
+}
@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use dynomite::AttributeValue;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    error::{ApiError, FieldError},
+    guestbook::{entry::Entry, reactions::ReactionCounts, store::GuestbookStore},
+};
+
+/// The file formats the guestbook can be exported as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ExportFormat {
+    /// One JSON object per line, which can be imported again.
+    #[default]
+    Jsonl,
+
+    /// A spreadsheet-friendly table, which can't be imported.
+    Csv,
+}
+
+impl ExportFormat {
+    pub(crate) fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Jsonl => "application/jsonl; charset=utf-8",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    pub(crate) fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Csv => "csv",
+        }
+    }
+
+    /// The file's first line, before any entries.
+    pub(crate) fn header(&self) -> Option<String> {
+        match self {
+            ExportFormat::Jsonl => None,
+            ExportFormat::Csv => Some(csv_header()),
+        }
+    }
+
+    /// The entry's line of the file, including its line ending.
+    pub(crate) fn line(&self, entry: Entry) -> Result<String> {
+        match self {
+            ExportFormat::Jsonl => Ok(format!("{}\n", entry_to_json(entry)?)),
+            ExportFormat::Csv => Ok(entry_to_csv(&entry)),
+        }
+    }
+}
+
+/// Turns an entry into the JSON object written on each line of an export.
+///
+/// This goes through the entry's DynamoDB item, so an export holds exactly what's
+/// stored, field names and all, and reads back in with [`entry_from_json`].
+pub(crate) fn entry_to_json(entry: Entry) -> Result<Value> {
+    let item: HashMap<String, AttributeValue> = entry.into();
+    attribute_to_json(AttributeValue::M(item))
+}
+
+/// Reads an entry back from one line of an export.
+pub(crate) fn entry_from_json(value: Value) -> Result<Entry> {
+    match json_to_attribute(value) {
+        AttributeValue::M(item) => Ok(Entry::try_from(item)?),
+        _ => Err(anyhow!("Each line must be a JSON object")),
+    }
+}
+
+fn attribute_to_json(value: AttributeValue) -> Result<Value> {
+    Ok(match value {
+        AttributeValue::S(string) => Value::String(string),
+        AttributeValue::N(number) => Value::Number(
+            number
+                .parse::<Number>()
+                .map_err(|_| anyhow!("{number} isn't a number"))?,
+        ),
+        AttributeValue::Bool(bool) => Value::Bool(bool),
+        AttributeValue::Null(_) => Value::Null,
+        AttributeValue::L(list) => Value::Array(
+            list.into_iter()
+                .map(attribute_to_json)
+                .collect::<Result<_>>()?,
+        ),
+        AttributeValue::M(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| Ok((key, attribute_to_json(value)?)))
+                .collect::<Result<Map<_, _>>>()?,
+        ),
+        // Entries don't use sets or binary data.
+        other => return Err(anyhow!("Can't export attribute {other:?}")),
+    })
+}
+
+fn json_to_attribute(value: Value) -> AttributeValue {
+    match value {
+        Value::String(string) => AttributeValue::S(string),
+        Value::Number(number) => AttributeValue::N(number.to_string()),
+        Value::Bool(bool) => AttributeValue::Bool(bool),
+        Value::Null => AttributeValue::Null(true),
+        Value::Array(list) => AttributeValue::L(list.into_iter().map(json_to_attribute).collect()),
+        Value::Object(map) => AttributeValue::M(
+            map.into_iter()
+                .map(|(key, value)| (key, json_to_attribute(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// The columns of a CSV export. Reactions are written as a JSON object in one column.
+const CSV_COLUMNS: [&str; 14] = [
+    "id",
+    "created_at",
+    "deleted_at",
+    "status",
+    "approved_at",
+    "qa",
+    "name",
+    "email",
+    "url",
+    "message",
+    "reply_text",
+    "replied_at",
+    "edited_at",
+    "reactions",
+];
+
+fn csv_header() -> String {
+    csv_row(CSV_COLUMNS.iter().map(|column| column.to_string()))
+}
+
+/// One line of a CSV export, for opening backups in a spreadsheet. CSV exports can't
+/// be imported.
+fn entry_to_csv(entry: &Entry) -> String {
+    let time = |time: Option<chrono::DateTime<chrono::Utc>>| {
+        time.map(|time| time.to_rfc3339()).unwrap_or_default()
+    };
+
+    csv_row([
+        entry.id.to_hyphenated().to_string(),
+        entry.created_at.to_rfc3339(),
+        time(entry.deleted_at),
+        serde_json::to_value(entry.status)
+            .ok()
+            .and_then(|status| status.as_str().map(str::to_string))
+            .unwrap_or_default(),
+        time(entry.approved_at),
+        entry.qa.to_string(),
+        entry.name.clone(),
+        entry.email.clone().unwrap_or_default(),
+        entry.url.clone().unwrap_or_default(),
+        entry.message.clone(),
+        entry.reply_text.clone().unwrap_or_default(),
+        time(entry.replied_at),
+        time(entry.edited_at),
+        serde_json::to_string(&ReactionCounts(entry.reactions.clone())).unwrap_or_default(),
+    ])
+}
+
+fn csv_row(fields: impl IntoIterator<Item = String>) -> String {
+    let fields = fields
+        .into_iter()
+        .map(|field| {
+            // Spreadsheets run cells that start like a formula, and visitors write the
+            // names and messages, so those cells are kept as text.
+            if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+                format!("'{field}")
+            } else {
+                field
+            }
+        })
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect::<Vec<_>>();
+
+    format!("{}\r\n", fields.join(","))
+}
+
+/// The lines of an import that couldn't be read as entries, each reported as a field
+/// named after its line number.
+#[derive(Debug)]
+pub(crate) struct ImportErrors(pub Vec<FieldError>);
+
+impl From<ImportErrors> for ApiError {
+    fn from(errors: ImportErrors) -> Self {
+        ApiError::bad_request("Some lines aren't guestbook entries.")
+            .with_reason("invalid_import")
+            .with_field_errors(errors.0)
+    }
+}
+
+/// What an import changed, or would change on a dry run.
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub(crate) struct ImportReport {
+    pub dry_run: bool,
+
+    /// Entries that weren't in the guestbook before.
+    pub created: Vec<Uuid>,
+
+    /// Existing entries that the import overwrote.
+    pub updated: Vec<Uuid>,
+
+    /// How many entries were already exactly as imported.
+    #[schema(example = "10")]
+    pub unchanged: usize,
+}
+
+/// Parses a JSON Lines export. Blank lines are skipped; if any other line isn't an
+/// entry, every bad line is reported and nothing is returned.
+pub(crate) fn parse_jsonl(body: &str) -> Result<Vec<Entry>, ImportErrors> {
+    let mut entries = vec![];
+    let mut errors = vec![];
+
+    for (index, line) in body.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entry = serde_json::from_str(line)
+            .map_err(anyhow::Error::from)
+            .and_then(entry_from_json);
+
+        match entry {
+            Ok(entry) => entries.push(entry),
+            Err(err) => errors.push(FieldError {
+                field: format!("line {}", index + 1),
+                code: "invalid_entry",
+                message: err.to_string(),
+            }),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(ImportErrors(errors));
+    }
+
+    Ok(entries)
+}
+
+/// Writes each entry over the stored entry with the same ID, creating it if there's
+/// none. Stored entries keep their reactions. With `dry_run`, nothing is written, but
+/// the report is the same.
+pub(crate) async fn import_entries(
+    store: &dyn GuestbookStore,
+    entries: Vec<Entry>,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let mut existing = store
+        .scan_entries()
+        .await?
+        .into_iter()
+        .map(|entry| (entry.id, entry))
+        .collect::<HashMap<_, _>>();
+
+    let mut report = ImportReport {
+        dry_run,
+        ..Default::default()
+    };
+
+    for mut entry in entries {
+        match existing.get(&entry.id) {
+            None => report.created.push(entry.id),
+            Some(stored) => {
+                // Reactions keep counting from where they are, whatever the import says.
+                entry.reactions = stored.reactions.clone();
+                if entry_to_json(stored.clone())? == entry_to_json(entry.clone())? {
+                    report.unchanged += 1;
+                    continue;
+                }
+                report.updated.push(entry.id);
+            }
+        }
+
+        if !dry_run {
+            store.put_entry(&entry).await?;
+        }
+
+        // Later lines with the same ID are compared against this one.
+        existing.insert(entry.id, entry);
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guestbook::{entry::EntryStatus, memory::InMemoryGuestbookStore};
+
+    fn entry() -> Entry {
+        Entry {
+            name: "lemon".to_string(),
+            message: "hello, \"world\"\nbye".to_string(),
+            email: Some("lemon@example.com".to_string()),
+            status: EntryStatus::Pending,
+            reactions: HashMap::from([("heart".to_string(), 2)]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_entries_round_trip_through_json() {
+        let entry = entry();
+        let json = entry_to_json(entry.clone()).unwrap();
+        assert_eq!(json["email"], "lemon@example.com");
+
+        let line = serde_json::to_string(&json).unwrap();
+        let parsed = parse_jsonl(&format!("\n{line}\n")).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(entry_to_json(parsed[0].clone()).unwrap(), json);
+    }
+
+    #[test]
+    fn test_bad_lines_are_all_reported() {
+        let line = serde_json::to_string(&entry_to_json(entry()).unwrap()).unwrap();
+        let errors = parse_jsonl(&format!("{line}\nnope\n[1]")).unwrap_err();
+        assert_eq!(
+            errors
+                .0
+                .iter()
+                .map(|error| error.field.as_str())
+                .collect::<Vec<_>>(),
+            vec!["line 2", "line 3"]
+        );
+    }
+
+    #[test]
+    fn test_csv_fields_are_quoted() {
+        let row = entry_to_csv(&entry());
+        assert!(row.contains(",pending,"));
+        assert!(row.contains("\"hello, \"\"world\"\"\nbye\""));
+        assert!(row.ends_with(",\"{\"\"❤️\"\":2}\"\r\n"));
+        assert_eq!(csv_header().split(',').count(), CSV_COLUMNS.len());
+    }
+
+    #[test]
+    fn test_csv_formulas_are_escaped() {
+        let mut entry = entry();
+        entry.name = "=HYPERLINK(\"https://example.com\")".to_string();
+        entry.message = "@SUM(A1:A2)".to_string();
+        entry.url = Some("-1+1".to_string());
+
+        let row = entry_to_csv(&entry);
+        assert!(row.contains(",\"'=HYPERLINK(\"\"https://example.com\"\")\","));
+        assert!(row.contains(",'@SUM(A1:A2),"));
+        assert!(row.contains(",'-1+1,"));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_reports_without_writing() {
+        let store = InMemoryGuestbookStore::default();
+        let unchanged = entry();
+        let mut updated = entry();
+        store.put_entry(&unchanged).await.unwrap();
+        store.put_entry(&updated).await.unwrap();
+        updated.message = "edited".to_string();
+        let created = entry();
+
+        let imported = vec![unchanged.clone(), updated.clone(), created.clone()];
+        let report = import_entries(&store, imported.clone(), true)
+            .await
+            .unwrap();
+        assert_eq!(report.created, vec![created.id]);
+        assert_eq!(report.updated, vec![updated.id]);
+        assert_eq!(report.unchanged, 1);
+        assert!(store.get_entry(&created.id).await.is_err());
+
+        import_entries(&store, imported, false).await.unwrap();
+        assert_eq!(
+            store.get_entry(&updated.id).await.unwrap().message,
+            "edited"
+        );
+        assert!(store.get_entry(&created.id).await.is_ok());
+    }
+}
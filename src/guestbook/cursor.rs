@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::guestbook::entry::Entry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Direction {
+    Forward,
+    Backward,
+}
+
+/// A position in the guestbook listing, handed to clients as an opaque string.
+///
+/// A cursor holds the `created_at` and `id` of the entry at the edge of a page,
+/// copied from the entry itself, along with the direction to keep reading in. It
+/// isn't DynamoDB's `LastEvaluatedKey`, and can't be passed back as an
+/// `ExclusiveStartKey`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Cursor {
+    #[serde(rename = "c")]
+    pub created_at: DateTime<Utc>,
+
+    #[serde(rename = "i")]
+    pub id: Uuid,
+
+    #[serde(rename = "d")]
+    pub direction: Direction,
+}
+
+impl Cursor {
+    pub(crate) fn new(entry: &Entry, direction: Direction) -> Self {
+        Self {
+            created_at: entry.created_at,
+            id: entry.id,
+            direction,
+        }
+    }
+
+    pub(crate) fn key(&self) -> (DateTime<Utc>, Uuid) {
+        (self.created_at, self.id)
+    }
+
+    pub(crate) fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).expect("Cursor is always serializable"))
+    }
+
+    pub(crate) fn decode(value: &str) -> Result<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(value).context("Invalid cursor")?;
+        serde_json::from_slice(&bytes).context("Invalid cursor")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor::new(&Entry::default(), Direction::Backward);
+        assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+    }
+
+    #[test]
+    fn test_cursor_rejects_garbage() {
+        assert!(Cursor::decode("not a cursor").is_err());
+        assert!(Cursor::decode(&URL_SAFE_NO_PAD.encode("{}")).is_err());
+    }
+}
@@ -0,0 +1,501 @@
+use std::collections::HashMap;
+
+use anyhow::{Error, Result};
+use async_trait::async_trait;
+use aws_sdk_dynamodb::{error::QueryError, model::ReturnValue, Client, SdkError};
+use chrono::{DateTime, Utc};
+use dynomite::{Attribute, AttributeValue};
+use uuid::Uuid;
+
+use crate::{
+    error::{Conflict, NotFound},
+    guestbook::{
+        entry::{Entry, EntryStatus},
+        reactions::Reaction,
+        store::{EntryCounts, EntryEdit, EntryRead, GuestbookStore},
+    },
+};
+
+pub(crate) const TABLE_NAME: &str = "jil-guestbook";
+
+/// The global secondary index that keeps entries sorted by `created_at`.
+pub(crate) const CREATED_AT_INDEX: &str = "listing-created_at-index";
+
+/// Every entry is written with this attribute set to [`LISTING_PARTITION`], so that
+/// the whole guestbook lives in a single partition of [`CREATED_AT_INDEX`].
+pub(crate) const LISTING_ATTRIBUTE: &str = "listing";
+pub(crate) const LISTING_PARTITION: &str = "guestbook";
+
+/// The table holding the running totals that [`EntryCounts`] are read from, as the
+/// attributes of a single item, so that listings don't count every entry.
+pub(crate) const COUNTS_TABLE_NAME: &str = "jil-guestbook-counts";
+
+/// The ID of the one item in [`COUNTS_TABLE_NAME`].
+const COUNTS_ID: &str = "entries";
+
+#[derive(Debug, Clone)]
+pub(crate) struct DynamoDbGuestbookStore {
+    client: Client,
+}
+
+impl DynamoDbGuestbookStore {
+    pub(crate) fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    /// Moves the running totals from counting `old` to counting `new`, where `None`
+    /// means no entry. The entry has already been written by now, so a failure is
+    /// logged rather than returned, and the totals stay off until an admin recounts.
+    async fn adjust_counts(&self, old: Option<&Entry>, new: Option<&Entry>) {
+        let old = count_attributes(&old.map(EntryCounts::of).unwrap_or_default());
+        let new = count_attributes(&new.map(EntryCounts::of).unwrap_or_default());
+
+        let changes: Vec<(&str, i64)> = new
+            .into_iter()
+            .zip(old)
+            .map(|((name, new), (_, old))| (name, new as i64 - old as i64))
+            .filter(|(_, change)| *change != 0)
+            .collect();
+
+        if changes.is_empty() {
+            return;
+        }
+
+        let additions: Vec<String> = changes
+            .iter()
+            .map(|(name, _)| format!("#{name} :{name}"))
+            .collect();
+
+        let mut update = self
+            .client
+            .update_item()
+            .table_name(COUNTS_TABLE_NAME)
+            .key("id", COUNTS_ID.to_string().into_attr())
+            .update_expression(format!("ADD {}", additions.join(", ")));
+        for (name, change) in changes {
+            update = update
+                .expression_attribute_names(format!("#{name}"), name)
+                .expression_attribute_values(format!(":{name}"), change.into_attr());
+        }
+
+        if let Err(err) = update.send().await {
+            log::error!(
+                "Couldn't update the guestbook counts, so they're off until a recount: {err:?}"
+            );
+        }
+    }
+}
+
+/// Builds an `ExclusiveStartKey` for [`CREATED_AT_INDEX`], which needs both the
+/// index key and the table key of the entry to start after.
+fn index_key(created_at: DateTime<Utc>, id: Uuid) -> HashMap<String, AttributeValue> {
+    HashMap::from([
+        ("id".to_string(), id.into_attr()),
+        ("created_at".to_string(), created_at.into_attr()),
+        (
+            LISTING_ATTRIBUTE.to_string(),
+            AttributeValue::S(LISTING_PARTITION.to_string()),
+        ),
+    ])
+}
+
+/// The attributes of the counts item, with the count each one holds.
+fn count_attributes(counts: &EntryCounts) -> [(&'static str, usize); 5] {
+    [
+        ("total", counts.total),
+        ("published", counts.published),
+        ("published_qa", counts.published_qa),
+        ("deleted", counts.deleted),
+        ("qa", counts.qa),
+    ]
+}
+
+/// Whether a query failed because [`CREATED_AT_INDEX`] hasn't been created yet, which
+/// DynamoDB reports as a validation error rather than a missing resource.
+fn is_missing_index(err: &QueryError) -> bool {
+    err.is_resource_not_found_exception()
+        || err
+            .message()
+            .map_or(false, |message| message.contains("specified index"))
+}
+
+#[async_trait]
+impl GuestbookStore for DynamoDbGuestbookStore {
+    async fn get_entry(&self, id: &Uuid) -> Result<Entry> {
+        let entry: Entry = self
+            .client
+            .query()
+            .table_name(TABLE_NAME)
+            .key_condition_expression("id = :value".to_string())
+            .expression_attribute_values(":value".to_string(), AttributeValue::S(id.to_string()))
+            .send()
+            .await?
+            .items
+            .unwrap()
+            .pop()
+            .ok_or_else(|| Error::new(NotFound(format!("No entry found with ID {id}"))))?
+            .try_into()?;
+
+        Ok(entry)
+    }
+
+    async fn scan_entries(&self) -> Result<Vec<Entry>> {
+        let mut entries = vec![];
+        let mut exclusive_start_key = None;
+
+        // A single scan stops at 1 MB of data, so keep going until DynamoDB
+        // stops handing back a LastEvaluatedKey.
+        loop {
+            let scan_output = self
+                .client
+                .scan()
+                .table_name(TABLE_NAME)
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await?;
+
+            for item in scan_output.items.unwrap_or_default() {
+                entries.push(Entry::try_from(item)?);
+            }
+
+            match scan_output.last_evaluated_key {
+                Some(key) if !key.is_empty() => exclusive_start_key = Some(key),
+                _ => break,
+            }
+        }
+
+        Ok(entries)
+    }
+
+    async fn read_entries(&self, read: &EntryRead) -> Result<Vec<Entry>> {
+        let mut entries = vec![];
+        let mut exclusive_start_key = read
+            .start_after
+            .map(|(created_at, id)| index_key(created_at, id));
+
+        // Unpublished and QA entries are filtered out after DynamoDB applies the
+        // limit, so a short page doesn't mean the index is exhausted.
+        loop {
+            let remaining = read.limit.map(|limit| (limit - entries.len()) as i32);
+
+            let query = self
+                .client
+                .query()
+                .table_name(TABLE_NAME)
+                .index_name(CREATED_AT_INDEX)
+                .key_condition_expression(format!("{LISTING_ATTRIBUTE} = :listing"))
+                .expression_attribute_values(
+                    ":listing",
+                    AttributeValue::S(LISTING_PARTITION.to_string()),
+                )
+                .scan_index_forward(read.ascending)
+                .set_exclusive_start_key(exclusive_start_key)
+                .set_limit(remaining)
+                .send()
+                .await;
+
+            let query_output = match query {
+                Ok(query_output) => query_output,
+                Err(SdkError::ServiceError { err, .. }) if is_missing_index(&err) => {
+                    log::warn!("{CREATED_AT_INDEX} doesn't exist yet, so scanning instead");
+                    return Ok(read.apply(self.scan_entries().await?));
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            for item in query_output.items.unwrap_or_default() {
+                let entry = Entry::try_from(item)?;
+                if entry.is_published() && (read.include_qa || !entry.qa) {
+                    entries.push(entry);
+                }
+            }
+
+            if read.limit.is_some_and(|limit| entries.len() >= limit) {
+                break;
+            }
+
+            match query_output.last_evaluated_key {
+                Some(key) if !key.is_empty() => exclusive_start_key = Some(key),
+                _ => break,
+            }
+        }
+
+        Ok(entries)
+    }
+
+    async fn count_entries(&self) -> Result<EntryCounts> {
+        let item = self
+            .client
+            .get_item()
+            .table_name(COUNTS_TABLE_NAME)
+            .key("id", COUNTS_ID.to_string().into_attr())
+            .send()
+            .await?
+            .item
+            .unwrap_or_default();
+
+        let count = |name: &str| -> Result<usize> {
+            let count = item.get(name).cloned().map(i64::from_attr).transpose()?;
+            Ok(count.unwrap_or_default().max(0) as usize)
+        };
+
+        Ok(EntryCounts {
+            total: count("total")?,
+            published: count("published")?,
+            published_qa: count("published_qa")?,
+            deleted: count("deleted")?,
+            qa: count("qa")?,
+        })
+    }
+
+    async fn recount_entries(&self) -> Result<EntryCounts> {
+        let mut counts = EntryCounts::default();
+        let mut exclusive_start_key = None;
+
+        // Only fetch the attributes the counts depend on, to keep the scan cheap.
+        loop {
+            let scan_output = self
+                .client
+                .scan()
+                .table_name(TABLE_NAME)
+                .projection_expression("deleted_at, qa, #status")
+                .expression_attribute_names("#status", "status")
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await?;
+
+            for mut item in scan_output.items.unwrap_or_default() {
+                let deleted = item
+                    .remove("deleted_at")
+                    .map(Option::<DateTime<Utc>>::from_attr)
+                    .transpose()?
+                    .flatten()
+                    .is_some();
+                let qa = item
+                    .remove("qa")
+                    .map(bool::from_attr)
+                    .transpose()?
+                    .unwrap_or_default();
+                let status = item
+                    .remove("status")
+                    .map(EntryStatus::from_attr)
+                    .transpose()?
+                    .unwrap_or_default();
+
+                counts.record(deleted, qa, status);
+            }
+
+            match scan_output.last_evaluated_key {
+                Some(key) if !key.is_empty() => exclusive_start_key = Some(key),
+                _ => break,
+            }
+        }
+
+        // Entries written during the scan can still leave the totals off by a little,
+        // which the next recount fixes.
+        let mut item: HashMap<String, AttributeValue> = count_attributes(&counts)
+            .into_iter()
+            .map(|(name, count)| (name.to_string(), (count as i64).into_attr()))
+            .collect();
+        item.insert("id".to_string(), COUNTS_ID.to_string().into_attr());
+
+        self.client
+            .put_item()
+            .table_name(COUNTS_TABLE_NAME)
+            .set_item(Some(item))
+            .send()
+            .await?;
+
+        Ok(counts)
+    }
+
+    async fn put_entry(&self, entry: &Entry) -> Result<()> {
+        let mut item: HashMap<String, AttributeValue> = entry.clone().into();
+        item.remove("id");
+        item.insert(
+            LISTING_ATTRIBUTE.to_string(),
+            AttributeValue::S(LISTING_PARTITION.to_string()),
+        );
+
+        // Every attribute is set rather than the item being replaced, so that an
+        // existing entry's reactions, which are only ever added to, can be left alone.
+        let reactions = item.remove("reactions");
+        let mut assignments = vec![];
+        let mut update = self
+            .client
+            .update_item()
+            .table_name(TABLE_NAME)
+            .key("id", entry.id.into_attr());
+        for (index, (name, value)) in item.into_iter().enumerate() {
+            assignments.push(format!("#a{index} = :a{index}"));
+            update = update
+                .expression_attribute_names(format!("#a{index}"), name)
+                .expression_attribute_values(format!(":a{index}"), value);
+        }
+        if let Some(reactions) = reactions {
+            assignments.push("reactions = if_not_exists(reactions, :reactions)".to_string());
+            update = update.expression_attribute_values(":reactions", reactions);
+        }
+
+        // The replaced entry comes back with the write, so the counts can be moved
+        // from it without a race.
+        let old = update
+            .update_expression(format!("SET {}", assignments.join(", ")))
+            .return_values(ReturnValue::AllOld)
+            .send()
+            .await?
+            .attributes
+            .filter(|attributes| !attributes.is_empty())
+            .map(Entry::try_from)
+            .transpose()?;
+
+        self.adjust_counts(old.as_ref(), Some(entry)).await;
+
+        Ok(())
+    }
+
+    async fn edit_entry(&self, edit: &EntryEdit) -> Result<Entry> {
+        let mut changes: Vec<(&str, AttributeValue)> = vec![
+            ("name", edit.name.clone().into_attr()),
+            ("message", edit.message.clone().into_attr()),
+            ("url", edit.url.clone().into_attr()),
+            ("email", edit.email.clone().into_attr()),
+            ("edited_at", edit.edited_at.into_attr()),
+        ];
+
+        // An unedited entry has no edit time, or a NULL one if it was written without it.
+        let mut condition = "attribute_exists(id) AND \
+            (attribute_not_exists(edited_at) OR attribute_type(edited_at, :null))"
+            .to_string();
+        let mut update = self
+            .client
+            .update_item()
+            .table_name(TABLE_NAME)
+            .key("id", edit.id.into_attr())
+            .expression_attribute_values(":null", AttributeValue::S("NULL".to_string()));
+
+        // Only an entry that's still approved goes back to pending, so that an edit
+        // can't undo an admin rejecting or deleting it in the meantime.
+        if edit.unapprove {
+            changes.push(("status", EntryStatus::Pending.into_attr()));
+            changes.push(("approved_at", None::<DateTime<Utc>>.into_attr()));
+            condition.push_str(" AND #status = :approved");
+            update = update
+                .expression_attribute_names("#status", "status")
+                .expression_attribute_values(":approved", EntryStatus::Approved.into_attr());
+        }
+
+        let mut assignments = vec![];
+        for (index, (name, value)) in changes.into_iter().enumerate() {
+            assignments.push(format!("#a{index} = :a{index}"));
+            update = update
+                .expression_attribute_names(format!("#a{index}"), name)
+                .expression_attribute_values(format!(":a{index}"), value);
+        }
+
+        let updated = update
+            .update_expression(format!("SET {}", assignments.join(", ")))
+            .condition_expression(condition)
+            .return_values(ReturnValue::AllOld)
+            .send()
+            .await;
+
+        let old = match updated {
+            Ok(output) => Entry::try_from(output.attributes.unwrap_or_default())?,
+            Err(SdkError::ServiceError { err, .. })
+                if err.is_conditional_check_failed_exception() =>
+            {
+                let id = edit.id;
+                return Err(Error::new(Conflict(format!(
+                    "The entry with ID {id} was changed before this edit"
+                ))));
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut entry = old.clone();
+        edit.apply(&mut entry);
+        self.adjust_counts(Some(&old), Some(&entry)).await;
+
+        Ok(entry)
+    }
+
+    async fn add_reaction(&self, id: &Uuid, reaction: Reaction) -> Result<u32> {
+        // ADD can only reach into the `reactions` map once it exists, so the first
+        // reaction on an entry creates the map instead. Either write can lose a race
+        // with another request doing the same, in which case the other one will work.
+        for _ in 0..2 {
+            let added = self
+                .client
+                .update_item()
+                .table_name(TABLE_NAME)
+                .key("id", id.into_attr())
+                .update_expression("ADD reactions.#reaction :one")
+                .condition_expression("attribute_exists(reactions)")
+                .expression_attribute_names("#reaction", reaction.key())
+                .expression_attribute_values(":one", 1_u32.into_attr())
+                .return_values(ReturnValue::UpdatedNew)
+                .send()
+                .await;
+
+            match added {
+                Ok(output) => {
+                    let count = output
+                        .attributes
+                        .and_then(|mut attributes| attributes.remove("reactions"))
+                        .map(HashMap::<String, u32>::from_attr)
+                        .transpose()?
+                        .and_then(|reactions| reactions.get(reaction.key()).copied())
+                        .unwrap_or(1);
+                    return Ok(count);
+                }
+                Err(SdkError::ServiceError { err, .. })
+                    if err.is_conditional_check_failed_exception() => {}
+                Err(err) => return Err(err.into()),
+            }
+
+            let created = self
+                .client
+                .update_item()
+                .table_name(TABLE_NAME)
+                .key("id", id.into_attr())
+                .update_expression("SET reactions = :reactions")
+                .condition_expression("attribute_exists(id) AND attribute_not_exists(reactions)")
+                .expression_attribute_values(
+                    ":reactions",
+                    HashMap::from([(reaction.key().to_string(), 1_u32)]).into_attr(),
+                )
+                .send()
+                .await;
+
+            match created {
+                Ok(_) => return Ok(1),
+                Err(SdkError::ServiceError { err, .. })
+                    if err.is_conditional_check_failed_exception() => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        // Both writes keep failing their conditions when there's no entry at all.
+        Err(Error::new(NotFound(format!("No entry found with ID {id}"))))
+    }
+
+    async fn remove_entry(&self, id: &Uuid) -> Result<()> {
+        let old = self
+            .client
+            .delete_item()
+            .table_name(TABLE_NAME)
+            .key("id", id.into_attr())
+            .return_values(ReturnValue::AllOld)
+            .send()
+            .await?
+            .attributes
+            .filter(|attributes| !attributes.is_empty())
+            .map(Entry::try_from)
+            .transpose()?;
+
+        self.adjust_counts(old.as_ref(), None).await;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{error::ApiError, guestbook::entry::Entry};
+
+/// Why an edit token wasn't accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EditRejection {
+    /// The token wasn't issued for this entry.
+    InvalidToken,
+
+    /// The token is right, but the entry is too old to edit.
+    WindowClosed,
+
+    /// The token was already used: each token is good for one edit.
+    AlreadyUsed,
+}
+
+impl EditRejection {
+    pub(crate) fn reason(&self) -> &'static str {
+        match self {
+            EditRejection::InvalidToken => "invalid_edit_token",
+            EditRejection::WindowClosed => "edit_window_closed",
+            EditRejection::AlreadyUsed => "edit_token_used",
+        }
+    }
+}
+
+impl Display for EditRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EditRejection::InvalidToken => {
+                f.write_str("This edit token isn't valid for this entry.")
+            }
+            EditRejection::WindowClosed => f.write_str("This entry can no longer be edited."),
+            EditRejection::AlreadyUsed => f.write_str("This entry has already been edited."),
+        }
+    }
+}
+
+impl From<EditRejection> for ApiError {
+    fn from(rejection: EditRejection) -> Self {
+        let message = rejection.to_string();
+        let error = match rejection {
+            EditRejection::AlreadyUsed => ApiError::conflict(&message),
+            EditRejection::InvalidToken | EditRejection::WindowClosed => {
+                ApiError::forbidden(&message)
+            }
+        };
+        error.with_reason(rejection.reason())
+    }
+}
+
+/// Issues and checks the tokens that let someone edit the entry they just wrote.
+///
+/// A token is an HMAC of the entry's ID and creation time, so it only works for that
+/// one entry, and only until the edit window after its creation closes. Editing the
+/// entry sets its edit time, which uses the token up. Nothing about tokens is stored.
+#[derive(Debug, Clone)]
+pub(crate) struct EditTokens {
+    secret: Vec<u8>,
+    window: Duration,
+}
+
+impl EditTokens {
+    pub(crate) fn new(secret: &[u8], window: Duration) -> Self {
+        Self {
+            secret: secret.to_vec(),
+            window,
+        }
+    }
+
+    /// Signs tokens with `GUESTBOOK_EDIT_SECRET` and accepts edits for
+    /// `GUESTBOOK_EDIT_WINDOW_MINUTES` (15 by default) after an entry is created.
+    ///
+    /// Without a secret, a random one is used, so tokens stop working when the
+    /// server restarts.
+    pub(crate) fn from_env() -> Self {
+        let secret = std::env::var("GUESTBOOK_EDIT_SECRET").unwrap_or_else(|_| {
+            log::warn!("GUESTBOOK_EDIT_SECRET isn't set; edit tokens won't survive a restart");
+            uuid::Uuid::new_v4().to_string()
+        });
+
+        let window_minutes = std::env::var("GUESTBOOK_EDIT_WINDOW_MINUTES")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(15);
+
+        Self::new(secret.as_bytes(), Duration::minutes(window_minutes))
+    }
+
+    fn mac(&self, entry: &Entry) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(entry.id.to_hyphenated().to_string().as_bytes());
+        mac.update(b":");
+        mac.update(entry.created_at.to_rfc3339().as_bytes());
+        mac
+    }
+
+    pub(crate) fn issue(&self, entry: &Entry) -> String {
+        hex::encode(self.mac(entry).finalize().into_bytes())
+    }
+
+    /// Checks that `token` was issued for `entry` and hasn't been used, and that the
+    /// entry can still be edited at `now`.
+    pub(crate) fn verify(
+        &self,
+        entry: &Entry,
+        token: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(), EditRejection> {
+        let token = hex::decode(token).map_err(|_| EditRejection::InvalidToken)?;
+
+        self.mac(entry)
+            .verify_slice(&token)
+            .map_err(|_| EditRejection::InvalidToken)?;
+
+        // Tokens are only issued for new entries, so an edited entry's token was used.
+        if entry.edited_at.is_some() {
+            return Err(EditRejection::AlreadyUsed);
+        }
+
+        if now > entry.created_at + self.window {
+            return Err(EditRejection::WindowClosed);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_verify_within_the_window() {
+        let tokens = EditTokens::new(b"secret", Duration::minutes(15));
+        let entry = Entry::default();
+        let token = tokens.issue(&entry);
+
+        assert_eq!(tokens.verify(&entry, &token, entry.created_at), Ok(()));
+        assert_eq!(
+            tokens.verify(&entry, &token, entry.created_at + Duration::minutes(16)),
+            Err(EditRejection::WindowClosed)
+        );
+    }
+
+    #[test]
+    fn test_tokens_only_work_for_their_entry() {
+        let tokens = EditTokens::new(b"secret", Duration::minutes(15));
+        let entry = Entry::default();
+        let other = Entry::default();
+
+        assert_eq!(
+            tokens.verify(&other, &tokens.issue(&entry), other.created_at),
+            Err(EditRejection::InvalidToken)
+        );
+        assert_eq!(
+            tokens.verify(&entry, "not hex", entry.created_at),
+            Err(EditRejection::InvalidToken)
+        );
+
+        let other_tokens = EditTokens::new(b"other secret", Duration::minutes(15));
+        assert_eq!(
+            tokens.verify(&entry, &other_tokens.issue(&entry), entry.created_at),
+            Err(EditRejection::InvalidToken)
+        );
+    }
+
+    #[test]
+    fn test_tokens_work_once() {
+        let tokens = EditTokens::new(b"secret", Duration::minutes(15));
+        let mut entry = Entry::default();
+        let token = tokens.issue(&entry);
+
+        entry.edited_at = Some(entry.created_at + Duration::minutes(1));
+        assert_eq!(
+            tokens.verify(&entry, &token, entry.created_at + Duration::minutes(2)),
+            Err(EditRejection::AlreadyUsed)
+        );
+
+        // Only the right token finds out that the entry was edited.
+        assert_eq!(
+            tokens.verify(&entry, "0123abcd", entry.created_at + Duration::minutes(2)),
+            Err(EditRejection::InvalidToken)
+        );
+    }
+}
@@ -1,34 +1,45 @@
 use std::{collections::HashMap, default, net};
 
-use anyhow::{Error, Result};
+use anyhow::Result;
 use chrono::DateTime;
-use dynomite::Item;
-use serde::{ser::SerializeStruct, Serialize};
+use dynomite::{Attribute, Item};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::{
     api::guestbook::GuestbookForm,
+    error::ValidationErrors,
+    guestbook::{actions::GuestbookAction, validation::validate},
     slack::{channel::SlackChannel, SlackApiRequest},
 };
 
-struct DummyStruct;
+/// Where an entry is in the moderation queue. Only approved entries are listed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Attribute, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryStatus {
+    /// Waiting for an admin to approve or reject it.
+    Pending,
 
-impl DummyStruct {
-    fn always_true<T, U>(_: &HashMap<T, U>) -> bool {
-        true
-    }
+    /// Entries written before the moderation queue existed have no status, and
+    /// were all published, so this is the default.
+    #[default]
+    Approved,
+
+    Rejected,
 }
 
-#[derive(Debug, Clone, Item, ToSchema)]
-#[schema(example = json!({
-    "id": "123e4567-e89b-12d3-a456-426614174000",
-    "created_at": "2024-08-14T23:50:04.648Z",
-    "url": "https://example.com",
-    "email": "john@example.com",
-    "message": "Just stopping by to say hello!",
-    "name": "John Appleseed",
-  }))]
+/// My response to a guestbook entry, shown underneath it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Reply {
+    #[schema(example = "Thanks for stopping by!")]
+    pub text: String,
+
+    pub replied_at: DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Item)]
 pub struct Entry {
     #[dynomite(partition_key)]
     pub id: Uuid,
@@ -51,39 +62,41 @@ pub struct Entry {
     #[dynomite(default)]
     pub qa: bool,
 
-    // This should be transparent to the database and to serde
     #[dynomite(default)]
-    #[dynomite(skip_serializing_if = "DummyStruct::always_true")]
-    pub __ser_options: HashMap<String, bool>,
-}
+    pub status: EntryStatus,
 
-impl Entry {
-    pub(crate) fn push_ser_option(&mut self, key: &str) {
-        self.__ser_options.insert(key.to_string(), true);
-    }
-}
+    #[dynomite(default)]
+    pub approved_at: Option<DateTime<chrono::Utc>>,
 
-impl Serialize for Entry {
-    fn serialize<S>(&self, serializer: S) -> std::prelude::v1::Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let mut state = serializer.serialize_struct("Entry", 7)?;
-        state.serialize_field("id", &self.id.to_hyphenated().to_string())?;
-        state.serialize_field("created_at", &self.created_at.to_rfc3339())?;
-        state.serialize_field("url", &self.url)?;
-        state.serialize_field("message", &self.message)?;
-        state.serialize_field("name", &self.name)?;
-
-        if self.__ser_options.contains_key("serialize_deleted_at") {
-            state.serialize_field("deleted_at", &self.deleted_at.map(|dt| dt.to_rfc3339()))?;
-        }
+    // The reply is stored flat and shown as a `reply` object; see `Entry::reply`.
+    #[dynomite(default)]
+    pub reply_text: Option<String>,
 
-        if self.__ser_options.contains_key("serialize_qa") {
-            state.serialize_field("qa", &self.qa)?;
+    #[dynomite(default)]
+    pub replied_at: Option<DateTime<chrono::Utc>>,
+
+    #[dynomite(default)]
+    pub edited_at: Option<DateTime<chrono::Utc>>,
+
+    /// How many times each reaction was left, keyed by `Reaction::key`.
+    #[dynomite(default)]
+    pub reactions: HashMap<String, u32>,
+}
+
+impl Entry {
+    pub(crate) fn reply(&self) -> Option<Reply> {
+        match (&self.reply_text, self.replied_at) {
+            (Some(text), Some(replied_at)) => Some(Reply {
+                text: text.clone(),
+                replied_at,
+            }),
+            _ => None,
         }
+    }
 
-        state.end()
+    /// Whether the entry belongs in public listings (QA entries aside).
+    pub(crate) fn is_published(&self) -> bool {
+        self.deleted_at.is_none() && self.status == EntryStatus::Approved
     }
 }
 
@@ -91,24 +104,25 @@ impl Entry {
     pub(crate) fn slack_api_request(&self, peer: Option<net::SocketAddr>) -> SlackApiRequest {
         let name = self.name.clone();
         let message = self.message.clone();
-        let id = self.id.to_hyphenated().to_string();
         let url = self.url.clone().unwrap_or_else(|| "N/A".to_string());
         let email = self.email.clone().unwrap_or_else(|| "N/A".to_string());
         let peer = peer
             .map(|peer| peer.to_string())
             .unwrap_or_else(|| "N/A".to_string());
+        let pending = if self.status == EntryStatus::Pending {
+            " (pending approval)"
+        } else {
+            ""
+        };
 
-        SlackApiRequest {
-            text: format!("Guestbook entry from {}: {}", self.name, self.message),
-            channel: SlackChannel::JilGuestbook,
-            blocks: serde_json::from_str(&format!(
-                r#"
+        let mut blocks: Vec<Map<String, Value>> = serde_json::from_str(&format!(
+            r#"
     [
 		{{
 			"type": "section",
 			"text": {{
 				"type": "mrkdwn",
-				"text": "*{name}:*"
+				"text": "*{name}:*{pending}"
 			}}
 		}},
 		{{
@@ -138,60 +152,89 @@ impl Entry {
 		}},
 		{{
 			"type": "divider"
-		}},
-		{{
-			"type": "actions",
-			"elements": [
-				{{
-					"type": "button",
-					"text": {{
-						"type": "plain_text",
-						"text": "View Online",
-						"emoji": true
-					}},
-					"value": "click_me_123",
-                    "url": "https://jameslittle.me/guestbook"
-				}},
-				{{
-					"type": "button",
-					"text": {{
-						"type": "plain_text",
-						"text": "Delete",
-						"emoji": true
-					}},
-					"value": "click_me_123",
-					"url": "https://api.jameslittle.me/{id}/delete"
-				}}
-			]
 		}}
 	]
             "#,
-            ))
-            .unwrap(),
+        ))
+        .unwrap();
+
+        blocks.push(self.slack_actions_block());
+
+        SlackApiRequest {
+            text: format!(
+                "Guestbook entry from {}{}: {}",
+                self.name, pending, self.message
+            ),
+            channel: SlackChannel::JilGuestbook,
+            blocks,
         }
     }
-}
 
-impl TryFrom<GuestbookForm> for Entry {
-    type Error = anyhow::Error;
+    /// The buttons at the bottom of the entry's Slack message, which depend on the
+    /// entry's current state. Each button carries the entry's ID as its `value`, and
+    /// is handled by `POST /slack/interactions`.
+    pub(crate) fn slack_actions_block(&self) -> Map<String, Value> {
+        let id = self.id.to_hyphenated().to_string();
 
-    fn try_from(value: GuestbookForm) -> Result<Self, Self::Error> {
-        if value.message.len() > 1200 {
-            return Err(Error::msg("Message must be <= 1200 letters."));
+        let button = |action: GuestbookAction, text: &str, style: Option<&str>| {
+            let mut button = json!({
+                "type": "button",
+                "text": {
+                    "type": "plain_text",
+                    "text": text,
+                    "emoji": true
+                },
+                "action_id": action.action_id(),
+                "value": id
+            });
+            if let Some(style) = style {
+                button["style"] = Value::from(style);
+            }
+            button
+        };
+
+        let mut elements = vec![json!({
+            "type": "button",
+            "text": {
+                "type": "plain_text",
+                "text": "View Online",
+                "emoji": true
+            },
+            "url": "https://jameslittle.me/guestbook"
+        })];
+
+        if self.status == EntryStatus::Pending {
+            elements.push(button(GuestbookAction::Approve, "Approve", Some("primary")));
+            elements.push(button(GuestbookAction::Reject, "Reject", None));
         }
 
-        if value.name.len() > 600 {
-            return Err(Error::msg("Name must be <= 600 characters"));
+        if self.deleted_at.is_some() {
+            elements.push(button(GuestbookAction::Restore, "Restore", None));
+        } else {
+            elements.push(button(GuestbookAction::Delete, "Delete", Some("danger")));
         }
 
+        json!({ "type": "actions", "elements": elements })
+            .as_object()
+            .unwrap()
+            .clone()
+    }
+}
+
+impl TryFrom<GuestbookForm> for Entry {
+    type Error = ValidationErrors;
+
+    fn try_from(value: GuestbookForm) -> Result<Self, Self::Error> {
+        let form = validate(&value)?;
+
         Ok(Entry {
             id: Uuid::new_v4(),
             created_at: chrono::Utc::now(),
             deleted_at: None,
-            url: value.url,
-            email: value.email,
-            message: value.message,
-            name: value.name,
+            url: form.url,
+            email: form.email,
+            message: form.message,
+            name: form.name,
             qa: value.qa,
             ..Default::default()
         })
@@ -209,7 +252,12 @@ impl Default for Entry {
             message: default::Default::default(),
             name: default::Default::default(),
             qa: default::Default::default(),
-            __ser_options: default::Default::default(),
+            status: default::Default::default(),
+            approved_at: default::Default::default(),
+            reply_text: default::Default::default(),
+            replied_at: default::Default::default(),
+            edited_at: default::Default::default(),
+            reactions: default::Default::default(),
         }
     }
 }
@@ -0,0 +1,180 @@
+use anyhow::Result;
+use chrono::{DateTime, TimeZone, Utc};
+use minijinja::context;
+use serde::Serialize;
+
+use crate::{
+    guestbook::{entry::Entry, views::PublicEntry},
+    templates::Templates,
+};
+
+/// How many of the newest entries a feed includes.
+pub(crate) const FEED_SIZE: usize = 50;
+
+const GUESTBOOK_URL: &str = "https://jameslittle.me/guestbook";
+
+const FEED_BASE_URL: &str = "https://api.jameslittle.me/guestbook";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FeedFormat {
+    Atom,
+    Rss,
+}
+
+impl FeedFormat {
+    fn template(&self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "guestbook/feed.atom.xml",
+            FeedFormat::Rss => "guestbook/feed.rss.xml",
+        }
+    }
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "feed.atom",
+            FeedFormat::Rss => "feed.rss",
+        }
+    }
+
+    pub(crate) fn content_type(&self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "application/atom+xml; charset=utf-8",
+            FeedFormat::Rss => "application/rss+xml; charset=utf-8",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct FeedItem {
+    id: String,
+    name: String,
+    url: Option<String>,
+    message: String,
+    created_at: String,
+    created_at_rfc2822: String,
+
+    /// The entry as HTML, which the feed template escapes again to embed it.
+    content_html: String,
+}
+
+/// A rendered feed, along with when its newest entry was written.
+#[derive(Debug)]
+pub(crate) struct Feed {
+    pub body: String,
+
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// Renders the given entries, newest first, as a feed.
+pub(crate) fn render_feed(
+    templates: &Templates,
+    format: FeedFormat,
+    entries: &[Entry],
+) -> Result<Feed> {
+    let last_modified = entries.iter().map(|entry| entry.created_at).max();
+    // An empty feed has nothing to date it by, so it uses a fixed time that keeps its
+    // body (and ETag) the same between requests.
+    let updated = last_modified.unwrap_or_else(|| Utc.timestamp(0, 0));
+
+    let items = entries
+        .iter()
+        .map(|entry| {
+            Ok(FeedItem {
+                id: entry.id.to_hyphenated().to_string(),
+                name: entry.name.clone(),
+                url: entry.url.clone(),
+                message: entry.message.clone(),
+                created_at: entry.created_at.to_rfc3339(),
+                created_at_rfc2822: entry.created_at.to_rfc2822(),
+                content_html: templates.render(
+                    "guestbook/feed_entry.html",
+                    context! { entry => PublicEntry::from(entry.clone()) },
+                )?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let body = templates.render(
+        format.template(),
+        context! {
+            entries => items,
+            updated => updated.to_rfc3339(),
+            updated_rfc2822 => updated.to_rfc2822(),
+            guestbook_url => GUESTBOOK_URL,
+            feed_url => format!("{}/{}", FEED_BASE_URL, format.file_name()),
+        },
+    )?;
+
+    Ok(Feed {
+        body,
+        last_modified,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, url: Option<&str>, created_at: &str) -> Entry {
+        Entry {
+            name: name.to_string(),
+            url: url.map(str::to_string),
+            message: "hello <there> & welcome".to_string(),
+            created_at: DateTime::parse_from_rfc3339(created_at)
+                .unwrap()
+                .with_timezone(&Utc),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_atom_feed() {
+        let templates = Templates::new("/nonexistent");
+        let entries = vec![
+            entry("Mat", Some("https://example.com"), "2024-04-05T16:11:03Z"),
+            entry("Paulo", None, "2023-07-13T13:04:48Z"),
+        ];
+
+        let feed = render_feed(&templates, FeedFormat::Atom, &entries).unwrap();
+
+        assert_eq!(feed.last_modified, Some(entries[0].created_at));
+        assert!(feed
+            .body
+            .contains("<updated>2024-04-05T16:11:03+00:00</updated>"));
+        assert!(feed
+            .body
+            .contains(&format!("<id>urn:uuid:{}</id>", entries[1].id)));
+        assert!(feed.body.contains("<uri>https:"));
+        // The HTML content is escaped once for HTML and once more for the XML.
+        assert!(feed
+            .body
+            .contains("hello &amp;lt;there&amp;gt; &amp;amp; welcome"));
+    }
+
+    #[test]
+    fn test_rss_feed() {
+        let templates = Templates::new("/nonexistent");
+        let entries = vec![entry("Paulo", None, "2023-07-13T13:04:48Z")];
+
+        let feed = render_feed(&templates, FeedFormat::Rss, &entries).unwrap();
+
+        assert!(feed
+            .body
+            .contains("<pubDate>Thu, 13 Jul 2023 13:04:48 +0000</pubDate>"));
+        assert!(feed.body.contains(&format!(
+            "<guid isPermaLink=\"false\">urn:uuid:{}</guid>",
+            entries[0].id
+        )));
+    }
+
+    #[test]
+    fn test_empty_feed() {
+        let templates = Templates::new("/nonexistent");
+        let feed = render_feed(&templates, FeedFormat::Atom, &[]).unwrap();
+        assert_eq!(feed.last_modified, None);
+        assert!(feed.body.contains("<feed"));
+        assert!(feed
+            .body
+            .contains("<updated>1970-01-01T00:00:00+00:00</updated>"));
+    }
+}
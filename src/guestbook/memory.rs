@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use anyhow::{Error, Result};
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{
+    error::{Conflict, NotFound},
+    guestbook::{
+        entry::{Entry, EntryStatus},
+        reactions::Reaction,
+        store::{EntryCounts, EntryEdit, EntryRead, GuestbookStore},
+    },
+};
+
+/// A guestbook store that keeps every entry in process memory.
+///
+/// Nothing is persisted, so each server starts with an empty guestbook.
+#[derive(Debug, Default)]
+pub(crate) struct InMemoryGuestbookStore {
+    entries: RwLock<HashMap<Uuid, Entry>>,
+}
+
+#[async_trait]
+impl GuestbookStore for InMemoryGuestbookStore {
+    async fn get_entry(&self, id: &Uuid) -> Result<Entry> {
+        self.entries
+            .read()
+            .await
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Error::new(NotFound(format!("No entry found with ID {id}"))))
+    }
+
+    async fn scan_entries(&self) -> Result<Vec<Entry>> {
+        Ok(self.entries.read().await.values().cloned().collect())
+    }
+
+    async fn read_entries(&self, read: &EntryRead) -> Result<Vec<Entry>> {
+        Ok(read.apply(self.entries.read().await.values().cloned()))
+    }
+
+    async fn count_entries(&self) -> Result<EntryCounts> {
+        // Counting entries in memory is cheap enough to do on every listing.
+        self.recount_entries().await
+    }
+
+    async fn put_entry(&self, entry: &Entry) -> Result<()> {
+        let mut entries = self.entries.write().await;
+        let mut entry = entry.clone();
+        if let Some(stored) = entries.get(&entry.id) {
+            entry.reactions = stored.reactions.clone();
+        }
+
+        entries.insert(entry.id, entry);
+        Ok(())
+    }
+
+    async fn edit_entry(&self, edit: &EntryEdit) -> Result<Entry> {
+        let mut entries = self.entries.write().await;
+        let id = edit.id;
+        let entry = entries
+            .get_mut(&id)
+            .ok_or_else(|| Error::new(NotFound(format!("No entry found with ID {id}"))))?;
+
+        if entry.edited_at.is_some() || (edit.unapprove && entry.status != EntryStatus::Approved) {
+            return Err(Error::new(Conflict(format!(
+                "The entry with ID {id} was changed before this edit"
+            ))));
+        }
+
+        edit.apply(entry);
+        Ok(entry.clone())
+    }
+
+    async fn add_reaction(&self, id: &Uuid, reaction: Reaction) -> Result<u32> {
+        let mut entries = self.entries.write().await;
+        let entry = entries
+            .get_mut(id)
+            .ok_or_else(|| Error::new(NotFound(format!("No entry found with ID {id}"))))?;
+
+        let count = entry
+            .reactions
+            .entry(reaction.key().to_string())
+            .or_default();
+        *count += 1;
+
+        Ok(*count)
+    }
+
+    async fn remove_entry(&self, id: &Uuid) -> Result<()> {
+        self.entries.write().await.remove(id);
+        Ok(())
+    }
+}
@@ -1,2 +1,15 @@
+pub(crate) mod actions;
+pub(crate) mod avatar;
+pub(crate) mod backup;
+pub(crate) mod cursor;
+pub(crate) mod dynamodb;
+pub(crate) mod edit;
 pub(crate) mod entry;
+pub(crate) mod feed;
+pub(crate) mod memory;
+pub(crate) mod moderation;
 pub(crate) mod queries;
+pub(crate) mod reactions;
+pub(crate) mod store;
+pub(crate) mod validation;
+pub(crate) mod views;
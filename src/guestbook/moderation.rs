@@ -0,0 +1,307 @@
+use std::{fmt::Debug, net::IpAddr, num::NonZeroU32};
+
+use governor::{DefaultKeyedRateLimiter, Quota, RateLimiter};
+use regex::{Regex, RegexBuilder};
+
+use crate::{api::guestbook::GuestbookForm, error::ApiError};
+
+/// Why a guestbook submission was turned away.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Rejection {
+    /// The hidden honeypot field was filled in, which only bots do.
+    Honeypot,
+
+    /// The name or message matched one of the banned patterns.
+    BannedContent,
+
+    TooManyLinks {
+        count: usize,
+        max: usize,
+    },
+
+    TooManySubmissions,
+}
+
+impl Rejection {
+    /// A stable, machine-readable name for the rejection.
+    pub(crate) fn reason(&self) -> &'static str {
+        match self {
+            Rejection::Honeypot => "honeypot",
+            Rejection::BannedContent => "banned_content",
+            Rejection::TooManyLinks { .. } => "too_many_links",
+            Rejection::TooManySubmissions => "too_many_submissions",
+        }
+    }
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rejection::Honeypot | Rejection::BannedContent => {
+                f.write_str("This entry looks like spam.")
+            }
+            Rejection::TooManyLinks { count, max } => write!(
+                f,
+                "Messages may contain at most {max} links, but this one has {count}."
+            ),
+            Rejection::TooManySubmissions => {
+                f.write_str("Too many entries have been submitted from this address.")
+            }
+        }
+    }
+}
+
+impl From<Rejection> for ApiError {
+    fn from(rejection: Rejection) -> Self {
+        ApiError::bad_request(&rejection.to_string()).with_reason(rejection.reason())
+    }
+}
+
+/// A guestbook submission as seen by the moderation rules.
+pub(crate) struct Submission<'a> {
+    pub form: &'a GuestbookForm,
+
+    /// The submitter's IP address, if it could be determined.
+    pub ip: Option<IpAddr>,
+}
+
+pub(crate) trait ModerationRule: Debug + Send + Sync {
+    fn check(&self, submission: &Submission) -> Result<(), Rejection>;
+}
+
+/// Rejects submissions that fill in the hidden `phone` field.
+#[derive(Debug)]
+pub(crate) struct HoneypotRule;
+
+impl ModerationRule for HoneypotRule {
+    fn check(&self, submission: &Submission) -> Result<(), Rejection> {
+        match &submission.form.phone {
+            Some(phone) if !phone.is_empty() => Err(Rejection::Honeypot),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Rejects submissions whose name or message matches any of the given patterns,
+/// ignoring case.
+#[derive(Debug)]
+pub(crate) struct BannedPatternRule {
+    patterns: Vec<Regex>,
+}
+
+impl BannedPatternRule {
+    pub(crate) fn new<'a>(
+        patterns: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, regex::Error> {
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| RegexBuilder::new(pattern).case_insensitive(true).build())
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { patterns })
+    }
+}
+
+impl ModerationRule for BannedPatternRule {
+    fn check(&self, submission: &Submission) -> Result<(), Rejection> {
+        let banned = self.patterns.iter().any(|pattern| {
+            pattern.is_match(&submission.form.name) || pattern.is_match(&submission.form.message)
+        });
+
+        if banned {
+            Err(Rejection::BannedContent)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Rejects messages containing more than `max` links.
+#[derive(Debug)]
+pub(crate) struct MaxLinksRule {
+    max: usize,
+    link: Regex,
+}
+
+impl MaxLinksRule {
+    pub(crate) fn new(max: usize) -> Self {
+        Self {
+            max,
+            link: RegexBuilder::new(r"https?://|www\.")
+                .case_insensitive(true)
+                .build()
+                .unwrap(),
+        }
+    }
+}
+
+impl ModerationRule for MaxLinksRule {
+    fn check(&self, submission: &Submission) -> Result<(), Rejection> {
+        let count = self.link.find_iter(&submission.form.message).count();
+
+        if count > self.max {
+            Err(Rejection::TooManyLinks {
+                count,
+                max: self.max,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Rejects submissions once an IP address has made too many of them. Submissions
+/// without an IP address aren't limited, rather than all sharing one limit.
+#[derive(Debug)]
+pub(crate) struct SubmissionsPerIpRule {
+    limiter: DefaultKeyedRateLimiter<IpAddr>,
+}
+
+impl SubmissionsPerIpRule {
+    pub(crate) fn per_hour(submissions: NonZeroU32) -> Self {
+        Self {
+            limiter: RateLimiter::keyed(Quota::per_hour(submissions)),
+        }
+    }
+}
+
+impl ModerationRule for SubmissionsPerIpRule {
+    fn check(&self, submission: &Submission) -> Result<(), Rejection> {
+        let Some(ip) = submission.ip else {
+            return Ok(());
+        };
+
+        self.limiter
+            .check_key(&ip)
+            .map_err(|_| Rejection::TooManySubmissions)
+    }
+}
+
+/// The moderation rules every guestbook submission passes through before it's saved.
+#[derive(Debug, Default)]
+pub(crate) struct ModerationPipeline {
+    rules: Vec<Box<dyn ModerationRule>>,
+}
+
+impl ModerationPipeline {
+    pub(crate) fn with_rule(mut self, rule: impl ModerationRule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Builds the standard pipeline, configured from the environment:
+    ///
+    /// - `GUESTBOOK_BANNED_PATTERNS`: newline-separated regular expressions
+    /// - `GUESTBOOK_MAX_LINKS`: the most links a message may contain (default 2)
+    /// - `GUESTBOOK_SUBMISSIONS_PER_HOUR`: the most entries one IP may submit (default 5)
+    pub(crate) fn from_env() -> Self {
+        let banned_patterns = std::env::var("GUESTBOOK_BANNED_PATTERNS").unwrap_or_default();
+
+        let max_links = std::env::var("GUESTBOOK_MAX_LINKS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(2);
+
+        let submissions_per_hour = std::env::var("GUESTBOOK_SUBMISSIONS_PER_HOUR")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(NonZeroU32::new(5).unwrap());
+
+        Self::default()
+            .with_rule(HoneypotRule)
+            .with_rule(SubmissionsPerIpRule::per_hour(submissions_per_hour))
+            .with_rule(
+                BannedPatternRule::new(
+                    banned_patterns
+                        .lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty()),
+                )
+                .expect("GUESTBOOK_BANNED_PATTERNS contains an invalid pattern"),
+            )
+            .with_rule(MaxLinksRule::new(max_links))
+    }
+
+    /// Runs every rule in order, stopping at the first rejection.
+    pub(crate) fn check(&self, submission: &Submission) -> Result<(), Rejection> {
+        self.rules
+            .iter()
+            .try_for_each(|rule| rule.check(submission))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form(name: &str, message: &str) -> GuestbookForm {
+        GuestbookForm {
+            name: name.to_string(),
+            message: message.to_string(),
+            email: None,
+            url: None,
+            qa: false,
+            phone: None,
+        }
+    }
+
+    fn check(rule: &dyn ModerationRule, form: &GuestbookForm) -> Result<(), Rejection> {
+        rule.check(&Submission {
+            form,
+            ip: Some(IpAddr::from([127, 0, 0, 1])),
+        })
+    }
+
+    #[test]
+    fn test_honeypot_rule() {
+        let mut filled = form("lemon", "hello");
+        filled.phone = Some("555-1234".to_string());
+        assert_eq!(check(&HoneypotRule, &filled), Err(Rejection::Honeypot));
+        assert_eq!(check(&HoneypotRule, &form("lemon", "hello")), Ok(()));
+    }
+
+    #[test]
+    fn test_banned_pattern_rule_ignores_case() {
+        let rule = BannedPatternRule::new(["casino", r"cheap \w+ pills"]).unwrap();
+        assert_eq!(
+            check(&rule, &form("CASINO bonus", "hello")),
+            Err(Rejection::BannedContent)
+        );
+        assert_eq!(
+            check(&rule, &form("lemon", "Cheap blue pills!")),
+            Err(Rejection::BannedContent)
+        );
+        assert_eq!(check(&rule, &form("lemon", "hello")), Ok(()));
+    }
+
+    #[test]
+    fn test_max_links_rule() {
+        let rule = MaxLinksRule::new(1);
+        assert_eq!(check(&rule, &form("lemon", "see https://a.com")), Ok(()));
+        assert_eq!(
+            check(&rule, &form("lemon", "see https://a.com and www.b.com")),
+            Err(Rejection::TooManyLinks { count: 2, max: 1 })
+        );
+    }
+
+    #[test]
+    fn test_submissions_per_ip_rule() {
+        let rule = SubmissionsPerIpRule::per_hour(NonZeroU32::new(2).unwrap());
+        let form = form("lemon", "hello");
+        assert_eq!(check(&rule, &form), Ok(()));
+        assert_eq!(check(&rule, &form), Ok(()));
+        assert_eq!(check(&rule, &form), Err(Rejection::TooManySubmissions));
+
+        let other_ip = Submission {
+            form: &form,
+            ip: Some(IpAddr::from([10, 0, 0, 1])),
+        };
+        assert_eq!(rule.check(&other_ip), Ok(()));
+
+        let no_ip = Submission {
+            form: &form,
+            ip: None,
+        };
+        assert_eq!(rule.check(&no_ip), Ok(()));
+    }
+}
@@ -1,91 +1,438 @@
-use anyhow::{Error, Result};
-use dynomite::AttributeValue;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-use crate::guestbook::entry::Entry;
+use crate::guestbook::{
+    cursor::{Cursor, Direction},
+    entry::{Entry, EntryStatus},
+    store::{EntryCounts, EntryRead, GuestbookStore},
+};
 
-pub(crate) async fn get_single_entry(
-    dynamodb: &aws_sdk_dynamodb::Client,
-    id: &Uuid,
-) -> Result<Entry> {
-    if cfg!(test) {
-        return Ok(Entry::default()); // Don't actually read from the database in tests
-    }
+/// The largest page a client can ask for with the `limit` parameter.
+pub(crate) const MAX_PAGE_SIZE: usize = 100;
 
-    let entry: Entry = dynamodb
-        .query()
-        .table_name("jil-guestbook")
-        .key_condition_expression("id = :value".to_string())
-        .expression_attribute_values(":value".to_string(), AttributeValue::S(id.to_string()))
-        .send()
-        .await?
-        .items
-        .unwrap()
-        .pop()
-        .ok_or_else(|| Error::msg(format!("No entry found with ID {id}")))?
-        .try_into()?;
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Order {
+    /// Oldest first.
+    #[default]
+    Asc,
+
+    /// Newest first.
+    Desc,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct PageRequest {
+    /// Only return entries after the entry with this ID.
+    pub after: Option<Uuid>,
 
-    Ok(entry)
+    pub cursor: Option<Cursor>,
+
+    /// The maximum number of entries to return. `None` returns everything.
+    pub limit: Option<usize>,
+
+    pub order: Order,
+}
+
+#[derive(Debug)]
+pub(crate) struct EntryPage {
+    pub entries: Vec<Entry>,
+
+    pub counts: EntryCounts,
+
+    pub next_cursor: Option<String>,
+
+    pub prev_cursor: Option<String>,
+}
+
+impl EntryPage {
+    fn empty(counts: EntryCounts) -> Self {
+        Self {
+            entries: vec![],
+            counts,
+            next_cursor: None,
+            prev_cursor: None,
+        }
+    }
 }
 
 pub(crate) async fn get_undeleted_entries(
-    dynamodb: &aws_sdk_dynamodb::Client,
-    after: Option<Uuid>,
+    store: &dyn GuestbookStore,
+    page: &PageRequest,
     include_qa: bool,
-) -> Result<(usize, Vec<Entry>)> {
-    let entries = if cfg!(test) {
-        vec![
-            // TODO: Add test data
-        ] // Don't actually read from the database in tests
+) -> Result<EntryPage> {
+    let counts = store.count_entries().await?;
+
+    // `after` always means "created after", whichever way the page is ordered.
+    let cursor = match (&page.cursor, page.after) {
+        (Some(cursor), _) => Some(cursor.clone()),
+        (None, Some(after)) => match store.get_entry(&after).await {
+            Ok(entry) => Some(Cursor::new(
+                &entry,
+                match page.order {
+                    Order::Asc => Direction::Forward,
+                    Order::Desc => Direction::Backward,
+                },
+            )),
+            // An unknown ID yields an empty page, like it always has.
+            Err(_) => return Ok(EntryPage::empty(counts)),
+        },
+        (None, None) => None,
+    };
+
+    let backward = cursor.as_ref().map(|cursor| cursor.direction) == Some(Direction::Backward);
+    let start_after = cursor.as_ref().map(Cursor::key);
+
+    let limit = page.limit.map(|limit| limit.clamp(1, MAX_PAGE_SIZE));
+
+    // Read one entry past the limit to find out whether there's another page.
+    let mut entries = store
+        .read_entries(&EntryRead {
+            ascending: (page.order == Order::Asc) != backward,
+            start_after,
+            limit: limit.map(|limit| limit + 1),
+            include_qa,
+        })
+        .await?;
+
+    let has_more = limit.is_some_and(|limit| entries.len() > limit);
+    if let Some(limit) = limit {
+        entries.truncate(limit);
+    }
+
+    // Whatever we started reading after is itself on the other side of the page.
+    let (more_after, more_before) = if backward {
+        entries.reverse();
+        (true, has_more)
     } else {
-        dynamodb
-            .scan()
-            .table_name("jil-guestbook")
-            .send()
-            .await?
-            .items
-            .ok_or_else(|| Error::msg("Could not get items from dynamodb"))?
-            .into_iter()
-            .map(Entry::try_from)
-            .collect::<Result<Vec<Entry>, _>>()?
+        (has_more, start_after.is_some())
     };
 
-    let total_size = entries.len();
+    let next_cursor = entries
+        .last()
+        .filter(|_| more_after)
+        .map(|entry| Cursor::new(entry, Direction::Forward).encode());
 
-    let mut filtered_entries: Vec<Entry> = entries
-        .iter()
-        .filter(|entry| entry.deleted_at.is_none() && (if include_qa { true } else { !entry.qa }))
-        .cloned()
-        .collect();
+    let prev_cursor = entries
+        .first()
+        .filter(|_| more_before)
+        .map(|entry| Cursor::new(entry, Direction::Backward).encode());
+
+    Ok(EntryPage {
+        entries,
+        counts,
+        next_cursor,
+        prev_cursor,
+    })
+}
+
+/// Which entries an admin listing shows. Every status except `deleted` leaves out
+/// deleted entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum StatusFilter {
+    /// Entries that appear on my blog.
+    Live,
+
+    Qa,
+
+    Deleted,
+
+    Pending,
+
+    Rejected,
+}
+
+impl StatusFilter {
+    fn matches(&self, entry: &Entry) -> bool {
+        let deleted = entry.deleted_at.is_some();
+        match self {
+            StatusFilter::Live => entry.is_published() && !entry.qa,
+            StatusFilter::Qa => !deleted && entry.qa,
+            StatusFilter::Deleted => deleted,
+            StatusFilter::Pending => !deleted && entry.status == EntryStatus::Pending,
+            StatusFilter::Rejected => !deleted && entry.status == EntryStatus::Rejected,
+        }
+    }
+}
 
-    filtered_entries.sort_by_key(|entry| entry.created_at);
+#[derive(Debug, Default)]
+pub(crate) struct AdminFilter {
+    pub status: Option<StatusFilter>,
 
-    filtered_entries = filtered_entries
+    /// Only include entries created at or after this time.
+    pub from: Option<DateTime<Utc>>,
+
+    /// Only include entries created before this time.
+    pub to: Option<DateTime<Utc>>,
+
+    /// Only include entries whose name or message contains this text, ignoring case.
+    pub search: Option<String>,
+}
+
+impl AdminFilter {
+    fn matches(&self, entry: &Entry) -> bool {
+        let search = self.search.as_ref().map(|search| search.to_lowercase());
+
+        self.status.is_none_or(|status| status.matches(entry))
+            && self.from.is_none_or(|from| entry.created_at >= from)
+            && self.to.is_none_or(|to| entry.created_at < to)
+            && search.is_none_or(|search| {
+                entry.name.to_lowercase().contains(&search)
+                    || entry.message.to_lowercase().contains(&search)
+            })
+    }
+}
+
+/// Returns every entry matching the filter, newest first, whatever its status.
+pub(crate) async fn get_filtered_entries(
+    store: &dyn GuestbookStore,
+    filter: &AdminFilter,
+) -> Result<Vec<Entry>> {
+    let mut entries: Vec<Entry> = store
+        .scan_entries()
+        .await?
         .into_iter()
-        .skip_while(|entry| {
-            if let Some(after) = after {
-                entry.id != after
-            } else {
-                false
-            }
-        })
-        .skip(1) // Don't return the element that matches the after thing
+        .filter(|entry| filter.matches(entry))
         .collect();
 
-    Ok((total_size, filtered_entries))
+    entries.sort_by_key(|entry| std::cmp::Reverse((entry.created_at, entry.id)));
+
+    Ok(entries)
 }
 
-pub async fn put_guestbook_entry(dynamodb: &aws_sdk_dynamodb::Client, entry: &Entry) -> Result<()> {
-    if cfg!(test) {
-        return Ok(()); // Don't actually write to the database in tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guestbook::memory::InMemoryGuestbookStore;
+
+    async fn store_with_entries(count: usize) -> InMemoryGuestbookStore {
+        let store = InMemoryGuestbookStore::default();
+        let now = chrono::Utc::now();
+        for i in 0..count {
+            store
+                .put_entry(&Entry {
+                    created_at: now + chrono::Duration::seconds(i as i64),
+                    message: i.to_string(),
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+        store
     }
 
-    dynamodb
-        .put_item()
-        .table_name("jil-guestbook")
-        .set_item(Some(entry.clone().into()))
-        .send()
-        .await?;
+    fn messages(page: &EntryPage) -> Vec<&str> {
+        page.entries
+            .iter()
+            .map(|entry| entry.message.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_page_without_limit_returns_everything() {
+        let store = store_with_entries(3).await;
+        let page = get_undeleted_entries(&store, &PageRequest::default(), false)
+            .await
+            .unwrap();
+        assert_eq!(messages(&page), vec!["0", "1", "2"]);
+        assert!(page.next_cursor.is_none());
+        assert!(page.prev_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_page_forward_and_backward() {
+        let store = store_with_entries(5).await;
+
+        let first = get_undeleted_entries(
+            &store,
+            &PageRequest {
+                limit: Some(2),
+                ..Default::default()
+            },
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(messages(&first), vec!["0", "1"]);
+        assert!(first.prev_cursor.is_none());
+
+        let second = get_undeleted_entries(
+            &store,
+            &PageRequest {
+                cursor: Some(Cursor::decode(first.next_cursor.as_ref().unwrap()).unwrap()),
+                limit: Some(2),
+                ..Default::default()
+            },
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(messages(&second), vec!["2", "3"]);
 
-    Ok(())
+        let back = get_undeleted_entries(
+            &store,
+            &PageRequest {
+                cursor: Some(Cursor::decode(second.prev_cursor.as_ref().unwrap()).unwrap()),
+                limit: Some(2),
+                ..Default::default()
+            },
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(messages(&back), vec!["0", "1"]);
+        assert!(back.prev_cursor.is_none());
+        assert!(back.next_cursor.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_page_in_descending_order() {
+        let store = store_with_entries(3).await;
+
+        let first = get_undeleted_entries(
+            &store,
+            &PageRequest {
+                limit: Some(2),
+                order: Order::Desc,
+                ..Default::default()
+            },
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(messages(&first), vec!["2", "1"]);
+
+        let second = get_undeleted_entries(
+            &store,
+            &PageRequest {
+                cursor: Some(Cursor::decode(first.next_cursor.as_ref().unwrap()).unwrap()),
+                limit: Some(2),
+                order: Order::Desc,
+                ..Default::default()
+            },
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(messages(&second), vec!["0"]);
+        assert!(second.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_page_after_id_in_descending_order() {
+        let store = store_with_entries(3).await;
+        let oldest = store
+            .read_entries(&EntryRead {
+                ascending: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap()[0]
+            .id;
+
+        let page = get_undeleted_entries(
+            &store,
+            &PageRequest {
+                after: Some(oldest),
+                order: Order::Desc,
+                ..Default::default()
+            },
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(messages(&page), vec!["2", "1"]);
+    }
+
+    #[tokio::test]
+    async fn test_page_counts() {
+        let store = store_with_entries(2).await;
+        for entry in [
+            Entry {
+                qa: true,
+                ..Default::default()
+            },
+            Entry {
+                deleted_at: Some(chrono::Utc::now()),
+                ..Default::default()
+            },
+        ] {
+            store.put_entry(&entry).await.unwrap();
+        }
+
+        let page = get_undeleted_entries(&store, &PageRequest::default(), false)
+            .await
+            .unwrap();
+        assert_eq!(page.counts.total, 4);
+        assert_eq!(page.counts.visible(false), 2);
+        assert_eq!(page.counts.visible(true), 3);
+        assert_eq!(page.counts.deleted, 1);
+        assert_eq!(page.counts.qa, 1);
+    }
+
+    #[tokio::test]
+    async fn test_filtered_entries() {
+        let store = store_with_entries(3).await;
+        let mut deleted = Entry {
+            name: "Deleted Lemon".to_string(),
+            deleted_at: Some(chrono::Utc::now()),
+            ..Default::default()
+        };
+        store.put_entry(&deleted).await.unwrap();
+
+        let entries = get_filtered_entries(
+            &store,
+            &AdminFilter {
+                status: Some(StatusFilter::Deleted),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(entries.len(), 1);
+
+        let entries = get_filtered_entries(
+            &store,
+            &AdminFilter {
+                search: Some("lemon".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(entries[0].id, deleted.id);
+
+        deleted.deleted_at = None;
+        store.put_entry(&deleted).await.unwrap();
+        let live = get_filtered_entries(
+            &store,
+            &AdminFilter {
+                status: Some(StatusFilter::Live),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(live.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_page_after_unknown_id_is_empty() {
+        let store = store_with_entries(3).await;
+        let page = get_undeleted_entries(
+            &store,
+            &PageRequest {
+                after: Some(Uuid::new_v4()),
+                ..Default::default()
+            },
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(page.entries.is_empty());
+    }
 }
@@ -0,0 +1,172 @@
+use std::{
+    collections::{hash_map, HashMap},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::{ser::SerializeMap, Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// The reactions visitors can leave on a guestbook entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub(crate) enum Reaction {
+    #[serde(rename = "👍")]
+    ThumbsUp,
+
+    #[serde(rename = "❤️", alias = "❤")]
+    Heart,
+
+    #[serde(rename = "😂")]
+    Laugh,
+
+    #[serde(rename = "👋")]
+    Wave,
+
+    #[serde(rename = "✨")]
+    Sparkles,
+}
+
+impl Reaction {
+    pub(crate) const ALL: [Reaction; 5] = [
+        Reaction::ThumbsUp,
+        Reaction::Heart,
+        Reaction::Laugh,
+        Reaction::Wave,
+        Reaction::Sparkles,
+    ];
+
+    /// The key the reaction's counter is stored under. Kept to plain ASCII so that
+    /// it's easy to work with in the AWS console.
+    pub(crate) fn key(&self) -> &'static str {
+        match self {
+            Reaction::ThumbsUp => "thumbs_up",
+            Reaction::Heart => "heart",
+            Reaction::Laugh => "laugh",
+            Reaction::Wave => "wave",
+            Reaction::Sparkles => "sparkles",
+        }
+    }
+
+    pub(crate) fn emoji(&self) -> &'static str {
+        match self {
+            Reaction::ThumbsUp => "👍",
+            Reaction::Heart => "❤️",
+            Reaction::Laugh => "😂",
+            Reaction::Wave => "👋",
+            Reaction::Sparkles => "✨",
+        }
+    }
+}
+
+/// Serializes an entry's stored reaction counters as a map from emoji to count,
+/// leaving out reactions nobody has left.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ReactionCounts(pub HashMap<String, u32>);
+
+impl Serialize for ReactionCounts {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let counts = Reaction::ALL
+            .iter()
+            .filter_map(|reaction| {
+                self.0
+                    .get(reaction.key())
+                    .filter(|count| **count > 0)
+                    .map(|count| (reaction.emoji(), count))
+            })
+            .collect::<Vec<_>>();
+
+        let mut map = serializer.serialize_map(Some(counts.len()))?;
+        for (emoji, count) in counts {
+            map.serialize_entry(emoji, count)?;
+        }
+        map.end()
+    }
+}
+
+/// How long a visitor has to wait before leaving the same reaction on the same
+/// entry again.
+const REMEMBER_FOR: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Remembers which IP addresses left which reactions, so that each address can only
+/// leave each reaction on an entry once a day.
+///
+/// This is kept in memory, so restarting the server forgets it.
+#[derive(Debug, Default)]
+pub(crate) struct ReactionLog {
+    seen: Mutex<HashMap<(String, Uuid, Reaction), Instant>>,
+}
+
+impl ReactionLog {
+    /// Records the reaction, or returns `false` if this address already left it.
+    pub(crate) fn try_record(&self, ip: &str, id: Uuid, reaction: Reaction) -> bool {
+        let now = Instant::now();
+        let mut seen = self.seen.lock().unwrap();
+        seen.retain(|_, recorded_at| now.duration_since(*recorded_at) < REMEMBER_FOR);
+
+        match seen.entry((ip.to_string(), id, reaction)) {
+            hash_map::Entry::Occupied(_) => false,
+            hash_map::Entry::Vacant(vacant) => {
+                vacant.insert(now);
+                true
+            }
+        }
+    }
+
+    /// Forgets a recorded reaction, for when it couldn't be saved after all.
+    pub(crate) fn forget(&self, ip: &str, id: Uuid, reaction: Reaction) {
+        self.seen
+            .lock()
+            .unwrap()
+            .remove(&(ip.to_string(), id, reaction));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reactions_deserialize_from_emoji() {
+        assert_eq!(
+            serde_json::from_str::<Reaction>("\"👍\"").unwrap(),
+            Reaction::ThumbsUp
+        );
+        assert_eq!(
+            serde_json::from_str::<Reaction>("\"❤\"").unwrap(),
+            Reaction::Heart
+        );
+        assert!(serde_json::from_str::<Reaction>("\"💩\"").is_err());
+    }
+
+    #[test]
+    fn test_reaction_counts_serialize_by_emoji() {
+        let counts = HashMap::from([
+            ("thumbs_up".to_string(), 2),
+            ("wave".to_string(), 0),
+            ("unknown".to_string(), 4),
+        ]);
+
+        assert_eq!(
+            serde_json::to_value(ReactionCounts(counts)).unwrap(),
+            serde_json::json!({ "👍": 2 })
+        );
+    }
+
+    #[test]
+    fn test_reaction_log_dedups_per_ip() {
+        let log = ReactionLog::default();
+        let id = Uuid::new_v4();
+
+        assert!(log.try_record("1.2.3.4", id, Reaction::Heart));
+        assert!(!log.try_record("1.2.3.4", id, Reaction::Heart));
+        assert!(log.try_record("1.2.3.4", id, Reaction::Wave));
+        assert!(log.try_record("5.6.7.8", id, Reaction::Heart));
+
+        log.forget("1.2.3.4", id, Reaction::Heart);
+        assert!(log.try_record("1.2.3.4", id, Reaction::Heart));
+    }
+}
@@ -0,0 +1,207 @@
+use std::fmt::Debug;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::guestbook::{
+    entry::{Entry, EntryStatus},
+    reactions::Reaction,
+};
+
+/// How many entries of each kind the store holds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub(crate) struct EntryCounts {
+    /// Every stored entry.
+    pub total: usize,
+
+    /// Published entries, not counting QA entries.
+    pub published: usize,
+
+    /// Published entries that were submitted as QA.
+    pub published_qa: usize,
+
+    /// Deleted entries, whatever their other fields say.
+    pub deleted: usize,
+
+    /// Undeleted entries that were submitted as QA, published or not.
+    pub qa: usize,
+}
+
+impl EntryCounts {
+    /// The counts of a store holding only this entry.
+    pub(crate) fn of(entry: &Entry) -> Self {
+        let mut counts = Self::default();
+        counts.record(entry.deleted_at.is_some(), entry.qa, entry.status);
+        counts
+    }
+
+    pub(crate) fn record(&mut self, deleted: bool, qa: bool, status: EntryStatus) {
+        self.total += 1;
+
+        if deleted {
+            self.deleted += 1;
+            return;
+        }
+
+        if qa {
+            self.qa += 1;
+        }
+
+        if status == EntryStatus::Approved {
+            if qa {
+                self.published_qa += 1;
+            } else {
+                self.published += 1;
+            }
+        }
+    }
+
+    /// The number of entries a public listing shows across all of its pages.
+    pub(crate) fn visible(&self, include_qa: bool) -> usize {
+        if include_qa {
+            self.published + self.published_qa
+        } else {
+            self.published
+        }
+    }
+}
+
+/// Parameters for reading published entries in `created_at` order.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct EntryRead {
+    pub ascending: bool,
+
+    /// Start reading just past the entry with this `created_at` and ID.
+    pub start_after: Option<(DateTime<Utc>, Uuid)>,
+
+    pub limit: Option<usize>,
+
+    pub include_qa: bool,
+}
+
+impl EntryRead {
+    /// Picks out and orders the entries this read returns from a set of stored
+    /// entries, for stores that can't do it as part of reading them.
+    pub(crate) fn apply(&self, entries: impl IntoIterator<Item = Entry>) -> Vec<Entry> {
+        let key = |entry: &Entry| (entry.created_at, entry.id);
+
+        let mut entries: Vec<Entry> = entries
+            .into_iter()
+            .filter(|entry| entry.is_published() && (self.include_qa || !entry.qa))
+            .collect();
+
+        entries.sort_by_key(key);
+
+        if !self.ascending {
+            entries.reverse();
+        }
+
+        if let Some(start_after) = self.start_after {
+            entries.retain(|entry| {
+                if self.ascending {
+                    key(entry) > start_after
+                } else {
+                    key(entry) < start_after
+                }
+            });
+        }
+
+        if let Some(limit) = self.limit {
+            entries.truncate(limit);
+        }
+
+        entries
+    }
+}
+
+/// The fields an edit through `PATCH /guestbook/{id}` changes. Everything else about
+/// the entry stays as it's stored.
+#[derive(Debug, Clone)]
+pub(crate) struct EntryEdit {
+    pub id: Uuid,
+
+    pub name: String,
+
+    pub message: String,
+
+    pub url: Option<String>,
+
+    pub email: Option<String>,
+
+    pub edited_at: DateTime<Utc>,
+
+    /// Also move the entry from approved back to pending, for servers that require
+    /// approval.
+    pub unapprove: bool,
+}
+
+impl EntryEdit {
+    /// Makes this edit to an entry read from the store.
+    pub(crate) fn apply(&self, entry: &mut Entry) {
+        entry.name = self.name.clone();
+        entry.message = self.message.clone();
+        entry.url = self.url.clone();
+        entry.email = self.email.clone();
+        entry.edited_at = Some(self.edited_at);
+
+        if self.unapprove {
+            entry.status = EntryStatus::Pending;
+            entry.approved_at = None;
+        }
+    }
+}
+
+/// The storage backend for guestbook entries.
+///
+/// Handlers reach the store through `AppState`, so they work the same way
+/// against DynamoDB in production and against memory in tests and local dev.
+#[async_trait]
+pub(crate) trait GuestbookStore: Debug + Send + Sync {
+    /// Returns the entry with the given ID, or an error if no such entry exists.
+    async fn get_entry(&self, id: &Uuid) -> Result<Entry>;
+
+    /// Returns every stored entry, including deleted and QA entries, in no
+    /// particular order.
+    async fn scan_entries(&self) -> Result<Vec<Entry>>;
+
+    /// Returns published entries sorted by `created_at`, as described by `read`.
+    async fn read_entries(&self, read: &EntryRead) -> Result<Vec<Entry>>;
+
+    /// Returns how many entries of each kind the store holds. Every public listing
+    /// asks for these, so a store that can't count cheaply keeps running totals as
+    /// entries are written, rather than reading every entry here.
+    async fn count_entries(&self) -> Result<EntryCounts>;
+
+    /// Counts every stored entry from scratch, replacing any running totals. This
+    /// reads the whole store, so only admins can ask for it.
+    async fn recount_entries(&self) -> Result<EntryCounts> {
+        let mut counts = EntryCounts::default();
+        for entry in self.scan_entries().await? {
+            counts.record(entry.deleted_at.is_some(), entry.qa, entry.status);
+        }
+        Ok(counts)
+    }
+
+    /// Writes the entry, replacing any existing entry with the same ID, except that an
+    /// existing entry keeps its reactions. Those only change through `add_reaction`,
+    /// so that writing an entry read earlier doesn't undo reactions added since.
+    async fn put_entry(&self, entry: &Entry) -> Result<()>;
+
+    /// Saves an edit and returns the edited entry. Each entry can only be edited once,
+    /// so this fails with `Conflict` if the stored entry already has an edit time, or
+    /// isn't approved anymore when the edit unapproves it. This must be atomic, so
+    /// that two requests can't both make the one edit.
+    async fn edit_entry(&self, edit: &EntryEdit) -> Result<Entry>;
+
+    /// Adds one to the entry's count of the given reaction and returns the new count.
+    /// This must be atomic, since visitors react concurrently.
+    async fn add_reaction(&self, id: &Uuid, reaction: Reaction) -> Result<u32>;
+
+    /// Permanently removes the entry with the given ID. Removing an entry that
+    /// doesn't exist is not an error.
+    async fn remove_entry(&self, id: &Uuid) -> Result<()>;
+}
@@ -0,0 +1,297 @@
+use std::fmt::Display;
+
+use unicode_segmentation::UnicodeSegmentation;
+use url::Url;
+
+use crate::{api::guestbook::GuestbookForm, error::ValidationErrors};
+
+/// The longest name allowed, in user-perceived characters (grapheme clusters).
+pub(crate) const MAX_NAME_LENGTH: usize = 600;
+
+/// The longest message allowed, in user-perceived characters (grapheme clusters).
+pub(crate) const MAX_MESSAGE_LENGTH: usize = 1200;
+
+/// The fields of a [`GuestbookForm`] once they've been checked and tidied up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ValidForm {
+    pub name: String,
+    pub message: String,
+    pub email: Option<String>,
+    pub url: Option<String>,
+}
+
+/// Checks every field of the form, trimming whitespace and normalising the email and
+/// URL. Blank optional fields become `None`.
+pub(crate) fn validate(form: &GuestbookForm) -> Result<ValidForm, ValidationErrors> {
+    let mut errors = ValidationErrors::default();
+
+    let name = required_text(&mut errors, "name", "Name", &form.name, MAX_NAME_LENGTH);
+    let message = required_text(
+        &mut errors,
+        "message",
+        "Message",
+        &form.message,
+        MAX_MESSAGE_LENGTH,
+    );
+
+    let email = optional(&form.email).and_then(|email| match normalize_email(email) {
+        Some(email) => Some(email),
+        None => {
+            errors.push(
+                "email",
+                "invalid_email",
+                "Email isn't a valid email address.",
+            );
+            None
+        }
+    });
+
+    let url = optional(&form.url).and_then(|url| match normalize_url(url) {
+        Ok(url) => Some(url),
+        Err(err) => {
+            errors.push("url", err.code(), err.to_string());
+            None
+        }
+    });
+
+    if !errors.0.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(ValidForm {
+        name,
+        message,
+        email,
+        url,
+    })
+}
+
+/// Checks and trims the text of a reply to an entry, which is limited to the same
+/// length as a message.
+pub(crate) fn validate_reply(text: &str) -> Result<String, ValidationErrors> {
+    let mut errors = ValidationErrors::default();
+    let text = required_text(&mut errors, "text", "Reply", text, MAX_MESSAGE_LENGTH);
+
+    if !errors.0.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(text)
+}
+
+fn required_text(
+    errors: &mut ValidationErrors,
+    field: &str,
+    label: &str,
+    value: &str,
+    max_length: usize,
+) -> String {
+    let value = value.trim();
+
+    if value.is_empty() {
+        errors.push(field, "required", format!("{label} must not be empty."));
+    } else if value.graphemes(true).count() > max_length {
+        errors.push(
+            field,
+            "too_long",
+            format!("{label} must be at most {max_length} characters."),
+        );
+    }
+
+    value.to_string()
+}
+
+fn optional(value: &Option<String>) -> Option<&str> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+/// A light syntax check: something before the `@`, a dotted domain after it, and no
+/// whitespace. Whether the address works is the sender's business.
+fn normalize_email(email: &str) -> Option<String> {
+    let (local, domain) = email.rsplit_once('@')?;
+
+    let valid = email.len() <= 254
+        && !local.is_empty()
+        && !email.chars().any(char::is_whitespace)
+        && domain
+            .split('.')
+            .all(|label| !label.is_empty() && !label.starts_with('-') && !label.ends_with('-'))
+        && domain.contains('.');
+
+    valid.then(|| format!("{}@{}", local, domain.to_lowercase()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UrlError {
+    UnsupportedScheme,
+    Invalid,
+}
+
+impl UrlError {
+    fn code(&self) -> &'static str {
+        match self {
+            UrlError::UnsupportedScheme => "unsupported_scheme",
+            UrlError::Invalid => "invalid_url",
+        }
+    }
+}
+
+impl Display for UrlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UrlError::UnsupportedScheme => f.write_str("URL must be an http or https link."),
+            UrlError::Invalid => f.write_str("URL isn't a valid web address."),
+        }
+    }
+}
+
+/// Turns what people type as their website into an absolute http(s) URL, adding
+/// `https://` when they leave the scheme off.
+fn normalize_url(url: &str) -> Result<String, UrlError> {
+    // `example.com:8080` parses with a scheme of `example.com`, so only treat the
+    // scheme as real when it's followed by `//` or isn't a port number.
+    let after_colon = url.split_once(':').map_or("", |(_, rest)| rest);
+    let has_scheme = url.contains("://") || !after_colon.starts_with(|c: char| c.is_ascii_digit());
+
+    let parsed = match Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => parsed,
+        Ok(_) if has_scheme => return Err(UrlError::UnsupportedScheme),
+
+        _ => Url::parse(&format!("https://{url}")).map_err(|_| UrlError::Invalid)?,
+    };
+
+    match parsed.host_str() {
+        Some(host) if host.contains('.') || host == "localhost" => Ok(parsed.to_string()),
+        _ => Err(UrlError::Invalid),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form(name: &str, message: &str, email: Option<&str>, url: Option<&str>) -> GuestbookForm {
+        GuestbookForm {
+            name: name.to_string(),
+            message: message.to_string(),
+            email: email.map(str::to_string),
+            url: url.map(str::to_string),
+            qa: false,
+            phone: None,
+        }
+    }
+
+    fn codes(result: Result<ValidForm, ValidationErrors>) -> Vec<(String, &'static str)> {
+        result
+            .unwrap_err()
+            .0
+            .into_iter()
+            .map(|error| (error.field, error.code))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_form_is_trimmed() {
+        assert_eq!(
+            validate(&form(
+                "  John ",
+                "\nhello\n",
+                Some(" John@Example.COM "),
+                Some("  ")
+            )),
+            Ok(ValidForm {
+                name: "John".to_string(),
+                message: "hello".to_string(),
+                email: Some("John@example.com".to_string()),
+                url: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_lengths_count_graphemes() {
+        let family = "👨‍👩‍👧‍👦";
+        assert!(family.len() > 1);
+        assert!(validate(&form(&family.repeat(MAX_NAME_LENGTH), "hi", None, None)).is_ok());
+        assert_eq!(
+            codes(validate(&form(
+                &family.repeat(MAX_NAME_LENGTH + 1),
+                "hi",
+                None,
+                None
+            ))),
+            vec![("name".to_string(), "too_long")]
+        );
+    }
+
+    #[test]
+    fn test_reply_lengths_count_graphemes() {
+        let family = "👨‍👩‍👧‍👦";
+        assert_eq!(
+            validate_reply(&format!(" {} ", family.repeat(MAX_MESSAGE_LENGTH))),
+            Ok(family.repeat(MAX_MESSAGE_LENGTH))
+        );
+        assert_eq!(
+            validate_reply(&family.repeat(MAX_MESSAGE_LENGTH + 1))
+                .unwrap_err()
+                .0[0]
+                .code,
+            "too_long"
+        );
+        assert_eq!(validate_reply("  ").unwrap_err().0[0].code, "required");
+    }
+
+    #[test]
+    fn test_all_errors_are_reported() {
+        assert_eq!(
+            codes(validate(&form(
+                " ",
+                "",
+                Some("nope"),
+                Some("javascript:alert(1)")
+            ))),
+            vec![
+                ("name".to_string(), "required"),
+                ("message".to_string(), "required"),
+                ("email".to_string(), "invalid_email"),
+                ("url".to_string(), "unsupported_scheme"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_urls_are_normalized() {
+        assert_eq!(
+            normalize_url("example.com"),
+            Ok("https://example.com/".to_string())
+        );
+        assert_eq!(
+            normalize_url("http://example.com/blog"),
+            Ok("http://example.com/blog".to_string())
+        );
+        assert_eq!(
+            normalize_url("example.com:8080/x"),
+            Ok("https://example.com:8080/x".to_string())
+        );
+        assert_eq!(
+            normalize_url("JavaScript:alert(1)"),
+            Err(UrlError::UnsupportedScheme)
+        );
+        assert_eq!(
+            normalize_url("ftp://example.com"),
+            Err(UrlError::UnsupportedScheme)
+        );
+        assert_eq!(normalize_url("not a url"), Err(UrlError::Invalid));
+    }
+
+    #[test]
+    fn test_emails() {
+        assert_eq!(normalize_email("a@b.co"), Some("a@b.co".to_string()));
+        assert_eq!(normalize_email("a@b"), None);
+        assert_eq!(normalize_email("@b.co"), None);
+        assert_eq!(normalize_email("a b@c.co"), None);
+    }
+}
@@ -0,0 +1,209 @@
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Serializer};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::guestbook::{
+    avatar::avatar_url,
+    entry::{Entry, EntryStatus, Reply},
+    reactions::ReactionCounts,
+};
+
+// Entries have always been serialized with `to_rfc3339`, which writes `+00:00`
+// rather than chrono's default `Z`.
+fn rfc3339<S: Serializer>(time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&time.to_rfc3339())
+}
+
+fn rfc3339_option<S: Serializer>(
+    time: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match time {
+        Some(time) => rfc3339(time, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// An entry as anyone can see it. Never includes the email.
+///
+/// Handlers turn the stored [`Entry`] into whichever view their endpoint documents, so
+/// each response has exactly the fields its schema lists.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[schema(example = json!({
+    "id": "123e4567-e89b-12d3-a456-426614174000",
+    "created_at": "2024-08-14T23:50:04.648+00:00",
+    "url": "https://example.com",
+    "message": "Just stopping by to say hello!",
+    "name": "John Appleseed",
+    "avatar_url": "https://gravatar.com/avatar/855f96e983f1f8e8be944692b6f719fd54329826cb62e98015efee8e2e071dd4?s=80&d=https%3A%2F%2Fapi.jameslittle.me%2Fguestbook%2F123e4567-e89b-12d3-a456-426614174000%2Favatar.svg",
+    "status": "approved",
+    "edited_at": null,
+    "reply": {
+        "text": "Thanks for stopping by!",
+        "replied_at": "2024-08-15T09:12:44.120Z",
+    },
+    "reactions": { "👍": 3, "❤️": 1 },
+}))]
+pub(crate) struct PublicEntry {
+    pub id: Uuid,
+
+    #[serde(serialize_with = "rfc3339")]
+    pub created_at: DateTime<Utc>,
+
+    pub url: Option<String>,
+
+    pub message: String,
+
+    pub name: String,
+
+    /// The author's Gravatar, or a generated identicon if they didn't leave an email.
+    pub avatar_url: String,
+
+    pub status: EntryStatus,
+
+    #[serde(serialize_with = "rfc3339_option")]
+    pub edited_at: Option<DateTime<Utc>>,
+
+    pub reply: Option<Reply>,
+
+    /// How many times each emoji reaction was left. Reactions nobody has left are
+    /// missing.
+    #[schema(value_type = std::collections::HashMap<String, u32>)]
+    pub reactions: ReactionCounts,
+}
+
+impl From<Entry> for PublicEntry {
+    fn from(entry: Entry) -> Self {
+        PublicEntry {
+            avatar_url: avatar_url(&entry),
+            reply: entry.reply(),
+            id: entry.id,
+            created_at: entry.created_at,
+            url: entry.url,
+            message: entry.message,
+            name: entry.name,
+            status: entry.status,
+            edited_at: entry.edited_at,
+            reactions: ReactionCounts(entry.reactions),
+        }
+    }
+}
+
+/// A single entry, along with where it is in its lifecycle. Still never includes the
+/// email.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub(crate) struct DetailedEntry {
+    #[serde(flatten)]
+    pub entry: PublicEntry,
+
+    #[serde(serialize_with = "rfc3339_option")]
+    pub deleted_at: Option<DateTime<Utc>>,
+
+    /// Whether the entry was submitted as a test, which keeps it off my blog.
+    pub qa: bool,
+
+    #[serde(serialize_with = "rfc3339_option")]
+    pub approved_at: Option<DateTime<Utc>>,
+}
+
+impl From<Entry> for DetailedEntry {
+    fn from(entry: Entry) -> Self {
+        DetailedEntry {
+            deleted_at: entry.deleted_at,
+            qa: entry.qa,
+            approved_at: entry.approved_at,
+            entry: entry.into(),
+        }
+    }
+}
+
+/// Everything about an entry, for admins only.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub(crate) struct AdminEntry {
+    #[serde(flatten)]
+    pub entry: DetailedEntry,
+
+    #[schema(example = "john@example.com")]
+    pub email: Option<String>,
+}
+
+impl From<Entry> for AdminEntry {
+    fn from(entry: Entry) -> Self {
+        AdminEntry {
+            email: entry.email.clone(),
+            entry: entry.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> Entry {
+        Entry {
+            name: "lemon".to_string(),
+            message: "hello".to_string(),
+            email: Some("lemon@example.com".to_string()),
+            qa: true,
+            ..Default::default()
+        }
+    }
+
+    fn keys(value: impl Serialize) -> Vec<String> {
+        let mut keys = serde_json::to_value(value)
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_only_admins_see_the_email() {
+        assert!(!keys(PublicEntry::from(entry())).contains(&"email".to_string()));
+        assert!(!keys(DetailedEntry::from(entry())).contains(&"email".to_string()));
+
+        let admin = serde_json::to_value(AdminEntry::from(entry())).unwrap();
+        assert_eq!(admin["email"], "lemon@example.com");
+        assert_eq!(admin["qa"], true);
+        assert_eq!(admin["name"], "lemon");
+    }
+
+    #[test]
+    fn test_views_add_fields() {
+        assert_eq!(
+            keys(PublicEntry::from(entry())),
+            vec![
+                "avatar_url",
+                "created_at",
+                "edited_at",
+                "id",
+                "message",
+                "name",
+                "reactions",
+                "reply",
+                "status",
+                "url"
+            ]
+        );
+
+        let mut detailed = keys(PublicEntry::from(entry()));
+        detailed.extend(["approved_at", "deleted_at", "qa"].map(str::to_string));
+        detailed.sort();
+        assert_eq!(keys(DetailedEntry::from(entry())), detailed);
+    }
+
+    #[test]
+    fn test_times_are_rfc3339() {
+        let entry = entry();
+        let created_at = entry.created_at.to_rfc3339();
+        let public = serde_json::to_value(PublicEntry::from(entry)).unwrap();
+        assert_eq!(public["created_at"], created_at);
+        assert!(public["edited_at"].is_null());
+    }
+}
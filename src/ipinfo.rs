@@ -1,6 +1,10 @@
 #![allow(dead_code)]
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
 use serde::Deserialize;
@@ -25,30 +29,83 @@ impl CachedIpInfoResponse {
     }
 }
 
+/// How many addresses the cache holds before it forgets the oldest.
+const CACHE_CAPACITY: usize = 4096;
+
+/// How long an address stays cached, so that visitors' addresses aren't kept for good.
+const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The addresses looked up recently, forgotten once they're older than [`CACHE_TTL`]
+/// or there are more than [`CACHE_CAPACITY`] of them.
+#[derive(Debug, Default)]
+struct IpInfoCache {
+    entries: HashMap<String, (IpInfo, Instant)>,
+
+    /// The cached addresses, oldest first.
+    order: VecDeque<String>,
+}
+
+impl IpInfoCache {
+    fn get(&self, ip: &str, now: Instant) -> Option<IpInfo> {
+        self.entries
+            .get(ip)
+            .filter(|(_, cached_at)| now.duration_since(*cached_at) < CACHE_TTL)
+            .map(|(ip_info, _)| ip_info.clone())
+    }
+
+    fn insert(&mut self, ip: String, ip_info: IpInfo, now: Instant) {
+        // Only an expired address is cached again, so this is rare.
+        if self.entries.insert(ip.clone(), (ip_info, now)).is_some() {
+            self.order.retain(|cached| *cached != ip);
+        }
+        self.order.push_back(ip);
+
+        while let Some(oldest) = self.order.front() {
+            let expired = self.entries.get(oldest).map_or(true, |(_, cached_at)| {
+                now.duration_since(*cached_at) >= CACHE_TTL
+            });
+            if !expired && self.order.len() <= CACHE_CAPACITY {
+                break;
+            }
+
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct CachedIpInfoClient {
     client: IpInfoApiClient,
-    cache: HashMap<String, IpInfo>,
+
+    /// Only locked to read or write it, never while looking an address up, so that a
+    /// slow lookup doesn't hold up any others.
+    cache: Mutex<IpInfoCache>,
 }
 
 impl CachedIpInfoClient {
     pub(crate) fn new(token: String) -> Self {
         Self {
             client: IpInfoApiClient::new(token),
-            cache: HashMap::new(),
+            cache: Mutex::new(IpInfoCache::default()),
         }
     }
 
-    pub(crate) async fn get_ip_info(&mut self, ip: &str) -> Result<CachedIpInfoResponse> {
-        if let Some(ip_info) = self.cache.get(ip) {
+    pub(crate) async fn get_ip_info(&self, ip: &str) -> Result<CachedIpInfoResponse> {
+        let cached = self.cache.lock().unwrap().get(ip, Instant::now());
+        if let Some(ip_info) = cached {
             return Ok(CachedIpInfoResponse::new(
-                ip_info.clone(),
+                ip_info,
                 CachedIpInfoResponseType::CacheHit,
             ));
         }
 
         let ip_info = self.client.get_ip_info(ip).await?;
-        self.cache.insert(ip.to_string(), ip_info.clone());
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(ip.to_string(), ip_info.clone(), Instant::now());
 
         Ok(CachedIpInfoResponse::new(
             ip_info,
@@ -96,6 +153,11 @@ impl IpInfo {
     pub(crate) fn loc_to_string(&self) -> String {
         format!("{}, {}, {}", self.city, self.region, self.country)
     }
+
+    /// The two-letter country code, like `US`.
+    pub(crate) fn country(&self) -> &str {
+        &self.country
+    }
 }
 
 #[cfg(test)]
@@ -113,7 +175,7 @@ mod tests {
     #[tokio::test]
     async fn it_works_with_cache() {
         dotenv::dotenv().ok();
-        let mut client = CachedIpInfoClient::new(std::env::var("IPINFO_KEY").unwrap());
+        let client = CachedIpInfoClient::new(std::env::var("IPINFO_KEY").unwrap());
         let ip_info = client.get_ip_info("73.253.33.205").await.unwrap();
         assert_eq!(ip_info.ip_info.ip, "73.253.33.205");
         assert_eq!(ip_info.response_type, CachedIpInfoResponseType::CacheMiss);
@@ -123,10 +185,41 @@ mod tests {
         assert_eq!(ip_info.response_type, CachedIpInfoResponseType::CacheHit);
     }
 
+    fn ip_info(ip: &str) -> IpInfo {
+        IpInfo {
+            ip: ip.to_string(),
+            city: "Boston".to_string(),
+            region: "Massachusetts".to_string(),
+            country: "US".to_string(),
+            loc: "42.3584,-71.0598".to_string(),
+            org: "AS7922 Comcast Cable Communications, LLC".to_string(),
+            postal: "02108".to_string(),
+            timezone: "America/New_York".to_string(),
+        }
+    }
+
+    #[test]
+    fn cache_forgets_old_addresses() {
+        let mut cache = IpInfoCache::default();
+        let now = Instant::now();
+        for n in 0..=CACHE_CAPACITY {
+            cache.insert(n.to_string(), ip_info(&n.to_string()), now);
+        }
+        assert!(cache.get("0", now).is_none());
+        assert_eq!(cache.get("1", now).unwrap().ip, "1");
+        assert_eq!(cache.entries.len(), CACHE_CAPACITY);
+
+        let later = now + CACHE_TTL;
+        assert!(cache.get("1", later).is_none());
+        cache.insert("1".to_string(), ip_info("1"), later);
+        assert_eq!(cache.entries.len(), 1);
+        assert_eq!(cache.order, ["1"]);
+    }
+
     #[tokio::test]
     async fn error_response() {
         dotenv::dotenv().ok();
-        let mut client = CachedIpInfoClient::new(std::env::var("IPINFO_KEY").unwrap());
+        let client = CachedIpInfoClient::new(std::env::var("IPINFO_KEY").unwrap());
         let ip_info = client.get_ip_info("not an ip").await;
         assert!(ip_info.is_err());
     }
@@ -24,11 +24,14 @@ use utoipa_scalar::{Scalar, Servable as ScalarServable};
 mod admin;
 mod api;
 mod blog;
+mod client_ip;
 mod error;
 mod guestbook;
 mod ipinfo;
+mod migrations;
 mod shortener;
 mod slack;
+mod templates;
 
 use util::*;
 use api::github::*;
@@ -40,29 +43,78 @@ use api::shortener::*;
 
 #[derive(Debug, Clone)]
 pub struct AppState {
-    dynamodb: Client,
+    guestbook: Arc<dyn guestbook::store::GuestbookStore>,
+
+    shortener: Arc<dyn shortener::store::ShortlinkStore>,
+
+    guestbook_moderation: Arc<guestbook::moderation::ModerationPipeline>,
+
+    // when set, new guestbook entries wait for an admin to approve them
+    // before they're listed or trigger a blog redeploy.
+    guestbook_require_approval: bool,
+
+    guestbook_reactions: Arc<guestbook::reactions::ReactionLog>,
+
+    guestbook_edit_tokens: Arc<guestbook::edit::EditTokens>,
+
+    templates: Arc<templates::Templates>,
 
     // a rate limiter with a fixed capacity of 10 requests per second.
     // this is a global rate limiter, so it will apply to all routes that
     // use it.
     rate_limiter: Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>,
 
-    ipinfo_cached_client: Arc<Mutex<ipinfo::CachedIpInfoClient>>,
+    ipinfo_cached_client: Arc<ipinfo::CachedIpInfoClient>,
 
     light_state: Arc<Mutex<String>>,
 
     openapi: String,
 }
 
-pub async fn run(listener: TcpListener) -> Result<Server, std::io::Error> {
-    env_logger::init_from_env(Env::default().default_filter_or("info"));
-    dotenv::dotenv().ok();
+/// Builds the guestbook and shortener stores.
+///
+/// Setting `STORAGE_BACKEND=memory` keeps everything in process memory, which
+/// is what the integration tests use and lets local development run without
+/// AWS credentials. Any other value (or none at all) uses DynamoDB.
+async fn build_stores() -> (
+    Arc<dyn guestbook::store::GuestbookStore>,
+    Arc<dyn shortener::store::ShortlinkStore>,
+) {
+    if std::env::var("STORAGE_BACKEND").as_deref() == Ok("memory") {
+        return (
+            Arc::new(guestbook::memory::InMemoryGuestbookStore::default()),
+            Arc::new(shortener::memory::InMemoryShortlinkStore::default()),
+        );
+    }
 
+    let client = dynamodb_client().await;
+
+    (
+        Arc::new(guestbook::dynamodb::DynamoDbGuestbookStore::new(client.clone())),
+        Arc::new(shortener::dynamodb::DynamoDbShortlinkStore::new(client)),
+    )
+}
+
+async fn dynamodb_client() -> Client {
     let region_provider = RegionProviderChain::default_provider().or_else("us-west-1");
     let config = aws_config::from_env().region(region_provider).load().await;
-    let client = Client::new(&config);
+    Client::new(&config)
+}
+
+/// Runs the DynamoDB migration called `name`, like `2026_10_18`.
+pub async fn migrate(name: &str) -> anyhow::Result<()> {
+    let _ = env_logger::try_init_from_env(Env::default().default_filter_or("info"));
+    dotenv::dotenv().ok();
+
+    migrations::run(&dynamodb_client().await, name).await
+}
 
-    
+pub async fn run(listener: TcpListener) -> Result<Server, std::io::Error> {
+    // Tests spawn several servers in one process, so the logger may already be set.
+    let _ = env_logger::try_init_from_env(Env::default().default_filter_or("info"));
+    dotenv::dotenv().ok();
+
+    let (guestbook_store, shortener_store) = build_stores().await;
 
     #[derive(OpenApi)]
     #[openapi(
@@ -79,19 +131,40 @@ pub async fn run(listener: TcpListener) -> Result<Server, std::io::Error> {
             healthcheck,
 
             post_slack,
+            post_slack_interactions,
             get_blog_deploy,
             get_github_stork_stars,
             
             get_guestbook,
+            get_guestbook_page,
+            get_guestbook_form,
+            get_guestbook_atom_feed,
+            get_guestbook_rss_feed,
             post_guestbook,
+            post_guestbook_reaction,
             get_guestbook_entry,
+            get_guestbook_avatar,
+            patch_guestbook_entry,
             delete_guestbook_entry,
+            delete_guestbook_entry_rest,
+            restore_guestbook_entry,
+            get_admin_guestbook,
+            export_guestbook,
+            import_guestbook,
+            recount_guestbook,
+            approve_guestbook_entry,
+            reject_guestbook_entry,
+            reply_guestbook_entry,
             
             get_light,
             set_light,
 
             create_entry,
             list_entries,
+            get_entry,
+            update_entry,
+            get_entry_stats,
+            resolve_shortlink,
             delete_entry,
             update_stats,
         ),
@@ -110,13 +183,19 @@ pub async fn run(listener: TcpListener) -> Result<Server, std::io::Error> {
     let openapi = ApiDoc::openapi();
 
     let app_state = AppState {
-        dynamodb: client,
+        guestbook: guestbook_store,
+        shortener: shortener_store,
+        guestbook_moderation: Arc::new(guestbook::moderation::ModerationPipeline::from_env()),
+        guestbook_require_approval: std::env::var("GUESTBOOK_REQUIRE_APPROVAL").as_deref() == Ok("true"),
+        guestbook_reactions: Arc::new(guestbook::reactions::ReactionLog::default()),
+        guestbook_edit_tokens: Arc::new(guestbook::edit::EditTokens::from_env()),
+        templates: Arc::new(templates::Templates::from_env()),
         rate_limiter: Arc::new(RateLimiter::direct(
             Quota::with_period(Duration::from_secs(10)).unwrap(),
         )),
-        ipinfo_cached_client: Arc::new(Mutex::new(ipinfo::CachedIpInfoClient::new(
+        ipinfo_cached_client: Arc::new(ipinfo::CachedIpInfoClient::new(
             std::env::var("IPINFO_KEY").unwrap(),
-        ))),
+        )),
         light_state: Arc::new(Mutex::new("off".to_string())),
         openapi: openapi.clone().to_json().unwrap()
     };
@@ -128,23 +207,49 @@ pub async fn run(listener: TcpListener) -> Result<Server, std::io::Error> {
             .wrap(Logger::new(r#"peer="%a" time="%t" request="%r" response_code=%s response_size_bytes=%b response_time_ms="%D" user_agent="%{User-Agent}i" "#))
             .wrap(NormalizePath::trim())
             .wrap(Cors::permissive())
+            // Registered first, since on the shortlink domain every path is a shortname.
+            .service(api::shortener::resolve_shortlink_host)
             .service(util::healthcheck)
             .service(util::root_redirect)
             .service(util::openapi_route)
             .service(Scalar::with_url("/docs", openapi.clone()))
             .service(api::github::get_github_stork_stars)
             .service(api::slack::post_slack)
+            .service(api::slack::post_slack_interactions)
             .service(api::guestbook::post_guestbook)
+            .service(api::guestbook::post_guestbook_reaction)
             .service(api::guestbook::get_guestbook)
+            .service(api::guestbook::get_guestbook_page)
+            // Registered before `get_guestbook_entry`, whose `/guestbook/{id}` would match these too.
+            .service(api::guestbook::get_guestbook_form)
+            .service(api::guestbook::get_guestbook_atom_feed)
+            .service(api::guestbook::get_guestbook_rss_feed)
             .service(api::guestbook::get_guestbook_entry)
+            .service(api::guestbook::get_guestbook_avatar)
+            .service(api::guestbook::patch_guestbook_entry)
             .service(api::shortener::list_entries)
+            .service(api::shortener::get_entry)
+            .service(api::shortener::resolve_shortlink)
             .service(api::home::set_light)
             .service(api::home::get_light)
             .service(web::scope("")
                 .wrap(HttpAuthentication::bearer(validate_admin))
+                // Guestbook imports are much bigger than the default 256 kB payload limit.
+                .app_data(web::PayloadConfig::new(32 * 1024 * 1024))
                 .service(api::guestbook::delete_guestbook_entry)
+                .service(api::guestbook::delete_guestbook_entry_rest)
+                .service(api::guestbook::restore_guestbook_entry)
+                .service(api::guestbook::get_admin_guestbook)
+                .service(api::guestbook::export_guestbook)
+                .service(api::guestbook::import_guestbook)
+                .service(api::guestbook::recount_guestbook)
+                .service(api::guestbook::approve_guestbook_entry)
+                .service(api::guestbook::reject_guestbook_entry)
+                .service(api::guestbook::reply_guestbook_entry)
                 .service(api::blog::get_blog_deploy)
                 .service(api::shortener::create_entry)
+                .service(api::shortener::update_entry)
+                .service(api::shortener::get_entry_stats)
                 .service(api::shortener::delete_entry)
                 .service(api::shortener::update_stats)
             )
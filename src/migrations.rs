@@ -0,0 +1,257 @@
+//! One-off changes to the DynamoDB tables, run by name with
+//! `cargo run --bin migrate -- <name>`, like `cargo run --bin migrate -- 2026_10_18`.
+
+use anyhow::{bail, Result};
+use aws_sdk_dynamodb::{
+    model::{
+        AttributeDefinition, AttributeValue, CreateGlobalSecondaryIndexAction,
+        GlobalSecondaryIndexUpdate, KeySchemaElement, KeyType, Projection, ProjectionType,
+        ProvisionedThroughput, ScalarAttributeType, TableStatus, TimeToLiveSpecification,
+    },
+    Client,
+};
+
+use crate::{
+    guestbook::{
+        dynamodb::{
+            DynamoDbGuestbookStore, COUNTS_TABLE_NAME, CREATED_AT_INDEX, LISTING_ATTRIBUTE,
+            TABLE_NAME,
+        },
+        store::GuestbookStore,
+    },
+    shortener::{
+        dynamodb::{
+            DynamoDbShortlinkStore, CLICKS_TABLE_NAME, TABLE_NAME as SHORTENER_TABLE_NAME,
+            TTL_ATTRIBUTE,
+        },
+        entry::normalize_shortname,
+        store::ShortlinkStore,
+    },
+};
+
+/// Runs the migration called `name`: its function name without the `migration_` prefix.
+pub(crate) async fn run(client: &Client, name: &str) -> Result<()> {
+    match name {
+        "2026_10_18" => migration_2026_10_18(client).await,
+        "2026_10_18_02" => migration_2026_10_18_02(client).await,
+        "2026_10_18_03" => migration_2026_10_18_03(client).await,
+        "2026_10_18_04" => migration_2026_10_18_04(client).await,
+        "2026_10_18_05" => migration_2026_10_18_05(client).await,
+        _ => bail!("There's no migration called {name}"),
+    }
+}
+
+/// Adds the index that guestbook listing queries instead of scanning the table,
+/// then rewrites every entry so it carries the constant `listing` attribute that
+/// the index is partitioned on.
+///
+/// Safe to run again to redo the backfill: adding the index then fails, and is skipped.
+async fn migration_2026_10_18(client: &Client) -> Result<()> {
+    let added = client
+        .update_table()
+        .table_name(TABLE_NAME)
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name(LISTING_ATTRIBUTE)
+                .attribute_type(ScalarAttributeType::S)
+                .build(),
+        )
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("created_at")
+                .attribute_type(ScalarAttributeType::S)
+                .build(),
+        )
+        .global_secondary_index_updates(
+            GlobalSecondaryIndexUpdate::builder()
+                .create(
+                    CreateGlobalSecondaryIndexAction::builder()
+                        .index_name(CREATED_AT_INDEX)
+                        .key_schema(
+                            KeySchemaElement::builder()
+                                .attribute_name(LISTING_ATTRIBUTE)
+                                .key_type(KeyType::Hash)
+                                .build(),
+                        )
+                        .key_schema(
+                            KeySchemaElement::builder()
+                                .attribute_name("created_at")
+                                .key_type(KeyType::Range)
+                                .build(),
+                        )
+                        .projection(
+                            Projection::builder()
+                                .projection_type(ProjectionType::All)
+                                .build(),
+                        )
+                        .provisioned_throughput(
+                            ProvisionedThroughput::builder()
+                                .read_capacity_units(1)
+                                .write_capacity_units(1)
+                                .build(),
+                        )
+                        .build(),
+                )
+                .build(),
+        )
+        .send()
+        .await;
+    if let Err(err) = added {
+        log::warn!("Could not add {CREATED_AT_INDEX}, so only backfilling: {err:?}");
+    }
+
+    // The store adds the `listing` attribute on every write.
+    let store = DynamoDbGuestbookStore::new(client.clone());
+    for entry in store.scan_entries().await? {
+        store.put_entry(&entry).await?;
+    }
+
+    Ok(())
+}
+
+/// Turns on TTL for the link shortener table, so DynamoDB deletes entries once the
+/// time in their TTL attribute (written by the store for entries with an
+/// `expires_at`) has passed.
+async fn migration_2026_10_18_02(client: &Client) -> Result<()> {
+    client
+        .update_time_to_live()
+        .table_name(SHORTENER_TABLE_NAME)
+        .time_to_live_specification(
+            TimeToLiveSpecification::builder()
+                .attribute_name(TTL_ATTRIBUTE)
+                .enabled(true)
+                .build(),
+        )
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+/// Creates the table shortlink clicks are logged to, keyed by shortname and sorted by
+/// when the click happened.
+async fn migration_2026_10_18_03(client: &Client) -> Result<()> {
+    client
+        .create_table()
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("shortname")
+                .key_type(KeyType::Hash)
+                .build(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("clicked_at")
+                .key_type(KeyType::Range)
+                .build(),
+        )
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("shortname")
+                .attribute_type(ScalarAttributeType::S)
+                .build(),
+        )
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("clicked_at")
+                .attribute_type(ScalarAttributeType::S)
+                .build(),
+        )
+        .provisioned_throughput(
+            ProvisionedThroughput::builder()
+                .read_capacity_units(1)
+                .write_capacity_units(1)
+                .build(),
+        )
+        .table_name(CLICKS_TABLE_NAME)
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+/// Creates the table that keeps the running entry counts listings report, waits for it
+/// to be ready, then fills it by counting every entry.
+///
+/// Safe to run again to redo the count: creating the table then fails, and is skipped.
+async fn migration_2026_10_18_04(client: &Client) -> Result<()> {
+    let created = client
+        .create_table()
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("id")
+                .key_type(KeyType::Hash)
+                .build(),
+        )
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("id")
+                .attribute_type(ScalarAttributeType::S)
+                .build(),
+        )
+        .provisioned_throughput(
+            ProvisionedThroughput::builder()
+                .read_capacity_units(1)
+                .write_capacity_units(1)
+                .build(),
+        )
+        .table_name(COUNTS_TABLE_NAME)
+        .send()
+        .await;
+    if let Err(err) = created {
+        log::warn!("Could not create {COUNTS_TABLE_NAME}, so only recounting: {err:?}");
+    }
+
+    loop {
+        let status = client
+            .describe_table()
+            .table_name(COUNTS_TABLE_NAME)
+            .send()
+            .await?
+            .table
+            .and_then(|table| table.table_status);
+        if status == Some(TableStatus::Active) {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+
+    let counts = DynamoDbGuestbookStore::new(client.clone())
+        .recount_entries()
+        .await?;
+    log::info!("Counted guestbook entries: {counts:?}");
+
+    Ok(())
+}
+
+/// Moves every shortlink saved with uppercase letters in its shortname to the lowercase
+/// shortname that lookups use. An entry whose lowercase shortname is already taken is
+/// left where it is, and logged. Logged clicks stay under the old shortname.
+async fn migration_2026_10_18_05(client: &Client) -> Result<()> {
+    let store = DynamoDbShortlinkStore::new(client.clone());
+
+    for mut entry in store.scan_entries().await? {
+        let shortname = normalize_shortname(&entry.shortname);
+        if shortname == entry.shortname {
+            continue;
+        }
+
+        let old_shortname = std::mem::replace(&mut entry.shortname, shortname);
+        if let Err(err) = store.insert_entry(&entry).await {
+            log::warn!(
+                "Could not move {old_shortname} to {}: {err:?}",
+                entry.shortname
+            );
+            continue;
+        }
+
+        client
+            .delete_item()
+            .table_name(SHORTENER_TABLE_NAME)
+            .key("shortname", AttributeValue::S(old_shortname))
+            .send()
+            .await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use dynomite::{Attribute, Item};
+use serde::Serialize;
+use url::Url;
+use utoipa::ToSchema;
+
+/// What kind of client followed a shortlink, guessed from its user agent.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Attribute,
+    Serialize,
+    ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum UserAgentClass {
+    Desktop,
+    Mobile,
+    Tablet,
+
+    /// Crawlers, link previews, and command-line tools.
+    Bot,
+
+    /// No user agent was sent.
+    #[default]
+    Unknown,
+}
+
+impl UserAgentClass {
+    pub(crate) fn from_user_agent(user_agent: Option<&str>) -> Self {
+        let Some(user_agent) = user_agent.map(str::to_lowercase) else {
+            return UserAgentClass::Unknown;
+        };
+
+        let has_any = |needles: &[&str]| needles.iter().any(|needle| user_agent.contains(needle));
+
+        if user_agent.is_empty() {
+            UserAgentClass::Unknown
+        } else if has_any(&[
+            "bot",
+            "crawler",
+            "spider",
+            "preview",
+            "curl/",
+            "wget/",
+            "python-requests",
+            "facebookexternalhit",
+        ]) {
+            UserAgentClass::Bot
+        } else if has_any(&["ipad", "tablet"]) {
+            UserAgentClass::Tablet
+        } else if has_any(&["mobile", "iphone", "android"]) {
+            UserAgentClass::Mobile
+        } else {
+            UserAgentClass::Desktop
+        }
+    }
+}
+
+/// One time a shortlink was followed. Clicks are stored in their own table, keyed by
+/// shortname and time, so that a shortlink's clicks over a date range are one query.
+#[derive(Debug, Clone, PartialEq, Eq, Item)]
+pub struct ClickEvent {
+    #[dynomite(partition_key)]
+    pub shortname: String,
+
+    #[dynomite(sort_key)]
+    pub clicked_at: DateTime<Utc>,
+
+    /// The host of the page the link was followed from, like `news.ycombinator.com`.
+    #[dynomite(default)]
+    pub referrer: Option<String>,
+
+    #[dynomite(default)]
+    pub user_agent: UserAgentClass,
+
+    /// The two-letter country code the click came from, according to IPinfo.
+    #[dynomite(default)]
+    pub country: Option<String>,
+}
+
+/// The lowercased host of the `Referer` header's URL, if it has one.
+pub(crate) fn referrer_host(referer: Option<&str>) -> Option<String> {
+    let url = Url::parse(referer?).ok()?;
+    url.host_str().map(str::to_lowercase)
+}
+
+/// Whether looking the address up could tell us anything. Loopback, private, and
+/// other reserved addresses don't have a country.
+pub(crate) fn is_public_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation())
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                // Unique local (fc00::/7) and link-local (fe80::/10) addresses.
+                || first & 0xfe00 == 0xfc00
+                || first & 0xffc0 == 0xfe80)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_agent_classes() {
+        let class = |user_agent| UserAgentClass::from_user_agent(user_agent);
+        assert_eq!(class(None), UserAgentClass::Unknown);
+        assert_eq!(class(Some("")), UserAgentClass::Unknown);
+        assert_eq!(
+            class(Some(
+                "Mozilla/5.0 (Macintosh; Intel Mac OS X 14_0) Safari/605.1.15"
+            )),
+            UserAgentClass::Desktop
+        );
+        assert_eq!(
+            class(Some(
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0) Mobile/15E148"
+            )),
+            UserAgentClass::Mobile
+        );
+        assert_eq!(
+            class(Some("Mozilla/5.0 (iPad; CPU OS 17_0) Mobile/15E148")),
+            UserAgentClass::Tablet
+        );
+        assert_eq!(
+            class(Some("Slackbot-LinkExpanding 1.0")),
+            UserAgentClass::Bot
+        );
+        assert_eq!(class(Some("curl/8.4.0")), UserAgentClass::Bot);
+    }
+
+    #[test]
+    fn test_referrer_hosts() {
+        assert_eq!(
+            referrer_host(Some("https://News.YCombinator.com/item?id=1")),
+            Some("news.ycombinator.com".to_string())
+        );
+        assert_eq!(referrer_host(Some("not a url")), None);
+        assert_eq!(referrer_host(None), None);
+    }
+
+    #[test]
+    fn test_public_ips() {
+        assert!(is_public_ip(&"73.253.33.205".parse().unwrap()));
+        assert!(is_public_ip(&"2606:4700::1111".parse().unwrap()));
+        assert!(!is_public_ip(&"127.0.0.1".parse().unwrap()));
+        assert!(!is_public_ip(&"192.168.1.10".parse().unwrap()));
+        assert!(!is_public_ip(&"::1".parse().unwrap()));
+        assert!(!is_public_ip(&"fd12::1".parse().unwrap()));
+    }
+}
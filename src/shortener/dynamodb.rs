@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+
+use anyhow::{Error, Result};
+use async_trait::async_trait;
+use aws_sdk_dynamodb::{
+    model::{AttributeValue, ReturnValue},
+    Client, SdkError,
+};
+use chrono::{DateTime, Utc};
+use dynomite::Attribute;
+
+use crate::{
+    error::{Conflict, NotFound},
+    shortener::{clicks::ClickEvent, entry::Entry, store::ShortlinkStore},
+};
+
+pub(crate) const TABLE_NAME: &str = "jil-link-shortener";
+
+/// The click log, partitioned by shortname and sorted by `clicked_at`.
+pub(crate) const CLICKS_TABLE_NAME: &str = "jil-link-shortener-clicks";
+
+/// Entries with an `expires_at` are also written with this attribute, holding the same
+/// time as seconds since the epoch, which is the format DynamoDB's TTL needs.
+pub(crate) const TTL_ATTRIBUTE: &str = "expires_at_ttl";
+
+#[derive(Debug, Clone)]
+pub(crate) struct DynamoDbShortlinkStore {
+    client: Client,
+}
+
+impl DynamoDbShortlinkStore {
+    pub(crate) fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+/// The entry's DynamoDB item, with its TTL attribute if it expires.
+fn entry_item(entry: &Entry) -> HashMap<String, AttributeValue> {
+    let mut item: HashMap<String, AttributeValue> = entry.clone().into();
+    if let Some(expires_at) = entry.expires_at {
+        item.insert(
+            TTL_ATTRIBUTE.to_string(),
+            AttributeValue::N(expires_at.timestamp().to_string()),
+        );
+    }
+    item
+}
+
+#[async_trait]
+impl ShortlinkStore for DynamoDbShortlinkStore {
+    async fn get_entry(&self, shortname: &str) -> Result<Entry> {
+        let get_item_output = self
+            .client
+            .get_item()
+            .table_name(TABLE_NAME)
+            .key("shortname", AttributeValue::S(shortname.to_string()))
+            .send()
+            .await?;
+
+        let item = get_item_output.item.ok_or_else(|| {
+            Error::new(NotFound(format!(
+                "No entry found with shortname {shortname}"
+            )))
+        })?;
+
+        let entry = Entry::try_from(item)?;
+
+        Ok(entry)
+    }
+
+    async fn scan_entries(&self) -> Result<Vec<Entry>> {
+        let mut entries = vec![];
+        let mut exclusive_start_key = None;
+
+        // A single scan stops at 1 MB of data, so keep going until DynamoDB
+        // stops handing back a LastEvaluatedKey.
+        loop {
+            let scan_output = self
+                .client
+                .scan()
+                .table_name(TABLE_NAME)
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await?;
+
+            for item in scan_output.items.unwrap_or_default() {
+                entries.push(Entry::try_from(item)?);
+            }
+
+            match scan_output.last_evaluated_key {
+                Some(key) if !key.is_empty() => exclusive_start_key = Some(key),
+                _ => break,
+            }
+        }
+
+        Ok(entries)
+    }
+
+    async fn insert_entry(&self, entry: &Entry) -> Result<()> {
+        let put = self
+            .client
+            .put_item()
+            .table_name(TABLE_NAME)
+            .set_item(Some(entry_item(entry)))
+            .condition_expression("attribute_not_exists(shortname)")
+            .send()
+            .await;
+
+        match put {
+            Ok(_) => Ok(()),
+            Err(SdkError::ServiceError { err, .. })
+                if err.is_conditional_check_failed_exception() =>
+            {
+                Err(Error::new(Conflict(format!(
+                    "Shortname {} is already taken",
+                    entry.shortname
+                ))))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn reclaim_entry(&self, entry: &Entry) -> Result<()> {
+        let put = self
+            .client
+            .put_item()
+            .table_name(TABLE_NAME)
+            .set_item(Some(entry_item(entry)))
+            // Undeleted entries have no `deleted_at`, or a null one, so only a deleted
+            // entry's is a string. Expired entries linger until DynamoDB gets around to
+            // deleting them.
+            .condition_expression(format!(
+                "attribute_not_exists(shortname) OR attribute_type(deleted_at, :string) OR {TTL_ATTRIBUTE} <= :now"
+            ))
+            .expression_attribute_values(":string", AttributeValue::S("S".to_string()))
+            .expression_attribute_values(
+                ":now",
+                AttributeValue::N(chrono::Utc::now().timestamp().to_string()),
+            )
+            .send()
+            .await;
+
+        match put {
+            Ok(_) => Ok(()),
+            Err(SdkError::ServiceError { err, .. })
+                if err.is_conditional_check_failed_exception() =>
+            {
+                Err(Error::new(Conflict(format!(
+                    "Shortname {} is already taken",
+                    entry.shortname
+                ))))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn update_entry(&self, entry: &Entry, expected_version: u32) -> Result<()> {
+        // Entries from before versioning have no `version` attribute, which counts as 0.
+        let condition = if expected_version == 0 {
+            "attribute_exists(shortname) AND (attribute_not_exists(#version) OR #version = :expected)"
+        } else {
+            "attribute_exists(shortname) AND #version = :expected"
+        };
+
+        let updated = self
+            .client
+            .update_item()
+            .table_name(TABLE_NAME)
+            .key("shortname", AttributeValue::S(entry.shortname.clone()))
+            .update_expression(
+                "SET longurl = :longurl, updated_at = :updated_at, #version = :version",
+            )
+            .condition_expression(condition)
+            .expression_attribute_names("#version", "version")
+            .expression_attribute_values(":longurl", entry.longurl.clone().into_attr())
+            .expression_attribute_values(":updated_at", entry.updated_at.into_attr())
+            .expression_attribute_values(":version", entry.version.into_attr())
+            .expression_attribute_values(":expected", expected_version.into_attr())
+            .send()
+            .await;
+
+        match updated {
+            Ok(_) => Ok(()),
+            Err(SdkError::ServiceError { err, .. })
+                if err.is_conditional_check_failed_exception() =>
+            {
+                // The condition also fails when the entry is missing.
+                self.get_entry(&entry.shortname).await?;
+                Err(Error::new(Conflict(format!(
+                    "Shortlink {} was changed by someone else",
+                    entry.shortname
+                ))))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn delete_entry(&self, shortname: &str, deleted_at: DateTime<Utc>) -> Result<Entry> {
+        let updated = self
+            .client
+            .update_item()
+            .table_name(TABLE_NAME)
+            .key("shortname", AttributeValue::S(shortname.to_string()))
+            .update_expression("SET deleted_at = :deleted_at")
+            // Without this, SET would create an entry with nothing but a `deleted_at`.
+            .condition_expression("attribute_exists(shortname)")
+            .expression_attribute_values(":deleted_at", deleted_at.into_attr())
+            .return_values(ReturnValue::AllNew)
+            .send()
+            .await;
+
+        match updated {
+            Ok(output) => Ok(Entry::try_from(output.attributes.unwrap_or_default())?),
+            Err(SdkError::ServiceError { err, .. })
+                if err.is_conditional_check_failed_exception() =>
+            {
+                Err(Error::new(NotFound(format!(
+                    "No entry found with shortname {shortname}"
+                ))))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn set_clicks(&self, shortname: &str, clicks: u32) -> Result<()> {
+        let updated = self
+            .client
+            .update_item()
+            .table_name(TABLE_NAME)
+            .key("shortname", AttributeValue::S(shortname.to_string()))
+            .update_expression("SET clicks = :clicks")
+            .condition_expression("attribute_exists(shortname)")
+            .expression_attribute_values(":clicks", clicks.into_attr())
+            .send()
+            .await;
+
+        match updated {
+            Ok(_) => Ok(()),
+            Err(SdkError::ServiceError { err, .. })
+                if err.is_conditional_check_failed_exception() =>
+            {
+                Err(Error::new(NotFound(format!(
+                    "No entry found with shortname {shortname}"
+                ))))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn record_click(&self, shortname: &str) -> Result<()> {
+        let updated = self
+            .client
+            .update_item()
+            .table_name(TABLE_NAME)
+            .key("shortname", AttributeValue::S(shortname.to_string()))
+            .update_expression("ADD clicks :one")
+            // Without this, ADD would create an entry with nothing but a click count.
+            .condition_expression("attribute_exists(shortname)")
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .send()
+            .await;
+
+        match updated {
+            Ok(_) => Ok(()),
+            Err(SdkError::ServiceError { err, .. })
+                if err.is_conditional_check_failed_exception() =>
+            {
+                Err(Error::new(NotFound(format!(
+                    "No entry found with shortname {shortname}"
+                ))))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn record_click_event(&self, event: &ClickEvent) -> Result<()> {
+        self.client
+            .put_item()
+            .table_name(CLICKS_TABLE_NAME)
+            .set_item(Some(event.clone().into()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    async fn click_events(
+        &self,
+        shortname: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<ClickEvent>> {
+        let mut events = vec![];
+        let mut exclusive_start_key = None;
+
+        loop {
+            let query_output = self
+                .client
+                .query()
+                .table_name(CLICKS_TABLE_NAME)
+                .key_condition_expression(
+                    "shortname = :shortname AND clicked_at BETWEEN :from AND :to",
+                )
+                .expression_attribute_values(":shortname", shortname.to_string().into_attr())
+                .expression_attribute_values(":from", from.into_attr())
+                .expression_attribute_values(":to", to.into_attr())
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await?;
+
+            for item in query_output.items.unwrap_or_default() {
+                let event = ClickEvent::try_from(item)?;
+                // BETWEEN includes `to`, which this shouldn't.
+                if event.clicked_at < to {
+                    events.push(event);
+                }
+            }
+
+            match query_output.last_evaluated_key {
+                Some(key) if !key.is_empty() => exclusive_start_key = Some(key),
+                _ => break,
+            }
+        }
+
+        Ok(events)
+    }
+}
@@ -3,7 +3,15 @@ use dynomite::Item;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-use crate::api::shortener::CreateEntryForm;
+use crate::{
+    api::shortener::CreateEntryForm,
+    error::ValidationErrors,
+    shortener::{
+        generate::{generate_shortname, generated_shortname_length},
+        validation::{validate_longurl, validate_schedule, validate_shortname},
+    },
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize, Item, ToSchema)]
 pub struct Entry {
     #[dynomite(partition_key)]
@@ -19,26 +27,84 @@ pub struct Entry {
 
     #[dynomite(default)]
     pub clicks: u32,
+
+    #[dynomite(default)]
+    #[serde(default)]
+    pub updated_at: Option<DateTime<chrono::Utc>>,
+
+    /// Goes up by one with every update, so that updates based on an old copy of the
+    /// entry can be turned away.
+    #[dynomite(default)]
+    #[serde(default)]
+    pub version: u32,
+
+    /// When the shortlink stops working. DynamoDB deletes expired entries a little
+    /// while later.
+    #[dynomite(default)]
+    #[serde(default)]
+    pub expires_at: Option<DateTime<chrono::Utc>>,
+
+    /// When the shortlink starts working. Until then, it's as if it doesn't exist.
+    #[dynomite(default)]
+    #[serde(default)]
+    pub not_before: Option<DateTime<chrono::Utc>>,
+}
+
+impl Entry {
+    pub(crate) fn has_expired(&self, now: DateTime<chrono::Utc>) -> bool {
+        self.expires_at
+            .map_or(false, |expires_at| expires_at <= now)
+    }
+
+    pub(crate) fn is_scheduled(&self, now: DateTime<chrono::Utc>) -> bool {
+        self.not_before.map_or(false, |not_before| not_before > now)
+    }
+
+    /// Whether the shortlink resolves right now: it isn't deleted or expired, and
+    /// its `not_before` has passed.
+    pub(crate) fn is_live(&self, now: DateTime<chrono::Utc>) -> bool {
+        self.deleted_at.is_none() && !self.has_expired(now) && !self.is_scheduled(now)
+    }
+}
+
+/// Shortnames are case-insensitive: they're saved in lowercase, and looked up by their
+/// lowercase form, so `Stork` and `stork` are the same shortlink.
+pub(crate) fn normalize_shortname(shortname: &str) -> String {
+    shortname.to_ascii_lowercase()
 }
 
 impl TryFrom<CreateEntryForm> for Entry {
-    type Error = anyhow::Error;
+    type Error = ValidationErrors;
 
+    /// Leaving out the shortname (or leaving it blank) makes up a random one.
     fn try_from(form: CreateEntryForm) -> Result<Self, Self::Error> {
-        if form.shortname.is_empty() {
-            return Err(anyhow::anyhow!("Received an empty shortname"));
-        }
+        let mut errors = ValidationErrors::default();
+
+        let shortname = match form.shortname.filter(|shortname| !shortname.is_empty()) {
+            Some(shortname) => {
+                validate_shortname(&mut errors, &shortname);
+                normalize_shortname(&shortname)
+            }
+            None => generate_shortname(generated_shortname_length()),
+        };
+
+        validate_longurl(&mut errors, &form.longurl);
+        validate_schedule(&mut errors, form.not_before, form.expires_at);
 
-        if form.longurl.is_empty() {
-            return Err(anyhow::anyhow!("Received an empty longurl"));
+        if !errors.0.is_empty() {
+            return Err(errors);
         }
 
         Ok(Entry {
-            shortname: form.shortname,
+            shortname,
             created_at: chrono::Utc::now(),
             deleted_at: None,
             longurl: form.longurl,
             clicks: 0,
+            updated_at: None,
+            version: 0,
+            expires_at: form.expires_at,
+            not_before: form.not_before,
         })
     }
 }
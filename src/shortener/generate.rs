@@ -0,0 +1,46 @@
+use uuid::Uuid;
+
+/// Only lowercase letters, since shortnames aren't case-sensitive.
+const ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// How many times to try generating an unused shortname before giving up.
+pub(crate) const MAX_GENERATE_ATTEMPTS: usize = 5;
+
+/// The length of generated shortnames: `SHORTLINK_GENERATED_LENGTH`, or 6 when it isn't
+/// set. Kept between 4 and 20 characters, since a random v4 UUID only has enough bits
+/// for about 23.
+pub(crate) fn generated_shortname_length() -> usize {
+    std::env::var("SHORTLINK_GENERATED_LENGTH")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(6)
+        .clamp(4, 20)
+}
+
+/// Makes up a random shortname of lowercase letters and digits, of the given length.
+pub(crate) fn generate_shortname(length: usize) -> String {
+    let mut bits = Uuid::new_v4().as_u128();
+
+    (0..length)
+        .map(|_| {
+            let c = ALPHABET[bits as usize % ALPHABET.len()] as char;
+            bits /= ALPHABET.len() as u128;
+            c
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_shortnames_are_lowercase_alphanumeric() {
+        let shortname = generate_shortname(8);
+        assert_eq!(shortname.len(), 8);
+        assert!(shortname
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()));
+        assert_ne!(generate_shortname(8), shortname);
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::{hash_map, HashMap};
+
+use anyhow::{Error, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::RwLock;
+
+use crate::{
+    error::{Conflict, NotFound},
+    shortener::{clicks::ClickEvent, entry::Entry, store::ShortlinkStore},
+};
+
+/// A shortlink store that keeps every entry in process memory.
+///
+/// Nothing is persisted, so each server starts with no shortlinks.
+#[derive(Debug, Default)]
+pub(crate) struct InMemoryShortlinkStore {
+    entries: RwLock<HashMap<String, Entry>>,
+    click_events: RwLock<Vec<ClickEvent>>,
+}
+
+#[async_trait]
+impl ShortlinkStore for InMemoryShortlinkStore {
+    async fn get_entry(&self, shortname: &str) -> Result<Entry> {
+        self.entries
+            .read()
+            .await
+            .get(shortname)
+            .cloned()
+            .ok_or_else(|| {
+                Error::new(NotFound(format!(
+                    "No entry found with shortname {shortname}"
+                )))
+            })
+    }
+
+    async fn scan_entries(&self) -> Result<Vec<Entry>> {
+        Ok(self.entries.read().await.values().cloned().collect())
+    }
+
+    async fn insert_entry(&self, entry: &Entry) -> Result<()> {
+        match self.entries.write().await.entry(entry.shortname.clone()) {
+            hash_map::Entry::Occupied(_) => Err(Error::new(Conflict(format!(
+                "Shortname {} is already taken",
+                entry.shortname
+            )))),
+            hash_map::Entry::Vacant(vacant) => {
+                vacant.insert(entry.clone());
+                Ok(())
+            }
+        }
+    }
+
+    async fn reclaim_entry(&self, entry: &Entry) -> Result<()> {
+        let mut entries = self.entries.write().await;
+        if let Some(stored) = entries.get(&entry.shortname) {
+            if stored.deleted_at.is_none() && !stored.has_expired(chrono::Utc::now()) {
+                return Err(Error::new(Conflict(format!(
+                    "Shortname {} is already taken",
+                    entry.shortname
+                ))));
+            }
+        }
+
+        entries.insert(entry.shortname.clone(), entry.clone());
+        Ok(())
+    }
+
+    async fn update_entry(&self, entry: &Entry, expected_version: u32) -> Result<()> {
+        let mut entries = self.entries.write().await;
+        let stored = entries.get_mut(&entry.shortname).ok_or_else(|| {
+            Error::new(NotFound(format!(
+                "No entry found with shortname {}",
+                entry.shortname
+            )))
+        })?;
+
+        if stored.version != expected_version {
+            return Err(Error::new(Conflict(format!(
+                "Shortlink {} was changed by someone else",
+                entry.shortname
+            ))));
+        }
+
+        stored.longurl = entry.longurl.clone();
+        stored.updated_at = entry.updated_at;
+        stored.version = entry.version;
+        Ok(())
+    }
+
+    async fn delete_entry(&self, shortname: &str, deleted_at: DateTime<Utc>) -> Result<Entry> {
+        let mut entries = self.entries.write().await;
+        let entry = entries.get_mut(shortname).ok_or_else(|| {
+            Error::new(NotFound(format!(
+                "No entry found with shortname {shortname}"
+            )))
+        })?;
+        entry.deleted_at = Some(deleted_at);
+        Ok(entry.clone())
+    }
+
+    async fn set_clicks(&self, shortname: &str, clicks: u32) -> Result<()> {
+        let mut entries = self.entries.write().await;
+        let entry = entries.get_mut(shortname).ok_or_else(|| {
+            Error::new(NotFound(format!(
+                "No entry found with shortname {shortname}"
+            )))
+        })?;
+        entry.clicks = clicks;
+        Ok(())
+    }
+
+    async fn record_click(&self, shortname: &str) -> Result<()> {
+        let mut entries = self.entries.write().await;
+        let entry = entries.get_mut(shortname).ok_or_else(|| {
+            Error::new(NotFound(format!(
+                "No entry found with shortname {shortname}"
+            )))
+        })?;
+        entry.clicks += 1;
+        Ok(())
+    }
+
+    async fn record_click_event(&self, event: &ClickEvent) -> Result<()> {
+        self.click_events.write().await.push(event.clone());
+        Ok(())
+    }
+
+    async fn click_events(
+        &self,
+        shortname: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<ClickEvent>> {
+        let mut events = self
+            .click_events
+            .read()
+            .await
+            .iter()
+            .filter(|event| {
+                event.shortname == shortname && event.clicked_at >= from && event.clicked_at < to
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        events.sort_by_key(|event| event.clicked_at);
+        Ok(events)
+    }
+}
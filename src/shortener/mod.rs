@@ -1,8 +1,15 @@
 // use actix_web::web;
 // use actix_web_httpauth::middleware::HttpAuthentication;
 
+pub(crate) mod clicks;
+pub(crate) mod dynamodb;
 pub(crate) mod entry;
+pub(crate) mod generate;
+pub(crate) mod memory;
 pub(crate) mod queries;
+pub(crate) mod stats;
+pub(crate) mod store;
+pub(crate) mod validation;
 // pub(self) mod methods;
 
 // use crate::validate_admin;
@@ -0,0 +1,183 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Duration, NaiveDate};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::shortener::clicks::{ClickEvent, UserAgentClass};
+
+/// How many referrers and countries are listed in stats.
+pub(crate) const TOP_LIMIT: usize = 10;
+
+/// The longest date range stats can be asked for.
+pub(crate) const MAX_STATS_DAYS: i64 = 366;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub(crate) struct DailyClicks {
+    #[schema(value_type = String, example = "2026-10-18")]
+    pub date: NaiveDate,
+
+    pub clicks: u32,
+}
+
+/// A referrer host or country, and how many clicks came from it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub(crate) struct TopValue {
+    #[schema(example = "news.ycombinator.com")]
+    pub name: String,
+
+    pub clicks: u32,
+}
+
+/// A shortlink's clicks between two dates, inclusive, in UTC.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub(crate) struct ClickStats {
+    #[schema(example = "stork")]
+    pub shortname: String,
+
+    #[schema(value_type = String, example = "2026-09-19")]
+    pub from: NaiveDate,
+
+    #[schema(value_type = String, example = "2026-10-18")]
+    pub to: NaiveDate,
+
+    pub total: u32,
+
+    /// One item for every day in the range, including days without clicks.
+    pub daily: Vec<DailyClicks>,
+
+    /// The most common referrer hosts, most clicks first. Clicks without a referrer
+    /// aren't counted here.
+    pub top_referrers: Vec<TopValue>,
+
+    /// The most common countries, most clicks first. Clicks whose country isn't known
+    /// aren't counted here.
+    pub top_countries: Vec<TopValue>,
+
+    /// How many clicks came from each kind of client.
+    #[schema(value_type = std::collections::HashMap<String, u32>)]
+    pub user_agents: BTreeMap<UserAgentClass, u32>,
+}
+
+/// Adds up the clicks that happened between `from` and `to`, inclusive.
+pub(crate) fn summarize(
+    shortname: &str,
+    events: &[ClickEvent],
+    from: NaiveDate,
+    to: NaiveDate,
+) -> ClickStats {
+    let days = (to - from).num_days() + 1;
+    let mut daily = (0..days)
+        .map(|day| (from + Duration::days(day), 0))
+        .collect::<BTreeMap<_, u32>>();
+    let mut referrers = HashMap::new();
+    let mut countries = HashMap::new();
+    let mut user_agents = BTreeMap::new();
+    let mut total = 0;
+
+    for event in events {
+        let Some(clicks) = daily.get_mut(&event.clicked_at.naive_utc().date()) else {
+            continue;
+        };
+
+        *clicks += 1;
+        total += 1;
+        *user_agents.entry(event.user_agent).or_insert(0) += 1;
+        if let Some(referrer) = &event.referrer {
+            *referrers.entry(referrer.clone()).or_insert(0) += 1;
+        }
+        if let Some(country) = &event.country {
+            *countries.entry(country.clone()).or_insert(0) += 1;
+        }
+    }
+
+    ClickStats {
+        shortname: shortname.to_string(),
+        from,
+        to,
+        total,
+        daily: daily
+            .into_iter()
+            .map(|(date, clicks)| DailyClicks { date, clicks })
+            .collect(),
+        top_referrers: top_values(referrers),
+        top_countries: top_values(countries),
+        user_agents,
+    }
+}
+
+fn top_values(counts: HashMap<String, u32>) -> Vec<TopValue> {
+    let mut values = counts
+        .into_iter()
+        .map(|(name, clicks)| TopValue { name, clicks })
+        .collect::<Vec<_>>();
+
+    // Ties are broken by name, so the order doesn't change between requests.
+    values.sort_by(|a, b| b.clicks.cmp(&a.clicks).then_with(|| a.name.cmp(&b.name)));
+    values.truncate(TOP_LIMIT);
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn event(day: u32, referrer: Option<&str>, country: Option<&str>) -> ClickEvent {
+        ClickEvent {
+            shortname: "stork".to_string(),
+            clicked_at: Utc.ymd(2026, 10, day).and_hms(12, 0, 0),
+            referrer: referrer.map(str::to_string),
+            user_agent: UserAgentClass::Desktop,
+            country: country.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        let events = vec![
+            event(1, Some("a.com"), Some("US")),
+            event(3, Some("b.com"), Some("US")),
+            event(3, Some("b.com"), None),
+            event(3, None, Some("CA")),
+            // Outside the range.
+            event(9, Some("c.com"), Some("FR")),
+        ];
+
+        let from = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2026, 10, 4).unwrap();
+        let stats = summarize("stork", &events, from, to);
+
+        assert_eq!(stats.total, 4);
+        assert_eq!(
+            stats.daily.iter().map(|day| day.clicks).collect::<Vec<_>>(),
+            vec![1, 0, 3, 0]
+        );
+        assert_eq!(
+            stats.top_referrers,
+            vec![
+                TopValue {
+                    name: "b.com".to_string(),
+                    clicks: 2
+                },
+                TopValue {
+                    name: "a.com".to_string(),
+                    clicks: 1
+                },
+            ]
+        );
+        assert_eq!(stats.top_countries[0].name, "US");
+        assert_eq!(stats.user_agents[&UserAgentClass::Desktop], 4);
+    }
+
+    #[test]
+    fn test_top_values_are_limited() {
+        let counts = (0..TOP_LIMIT as u32 + 5)
+            .map(|i| (format!("{i}.com"), i))
+            .collect();
+        let top = top_values(counts);
+        assert_eq!(top.len(), TOP_LIMIT);
+        assert_eq!(top[0].clicks, TOP_LIMIT as u32 + 4);
+    }
+}
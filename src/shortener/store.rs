@@ -0,0 +1,63 @@
+use std::fmt::Debug;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::shortener::{clicks::ClickEvent, entry::Entry};
+
+/// The storage backend for link shortener entries.
+///
+/// Handlers reach the store through `AppState`, so they work the same way
+/// against DynamoDB in production and against memory in tests and local dev.
+#[async_trait]
+pub(crate) trait ShortlinkStore: Debug + Send + Sync {
+    /// Returns the entry with the given shortname, or a `NotFound` error if no
+    /// such entry exists.
+    async fn get_entry(&self, shortname: &str) -> Result<Entry>;
+
+    /// Returns every stored entry, including deleted entries, in no
+    /// particular order.
+    async fn scan_entries(&self) -> Result<Vec<Entry>>;
+
+    /// Writes a new entry, or fails with a `Conflict` error if any entry (deleted or
+    /// not) already has its shortname. Checking and writing happen in one step, so two
+    /// requests can't both create the same shortname.
+    async fn insert_entry(&self, entry: &Entry) -> Result<()>;
+
+    /// Like `insert_entry`, but a deleted or expired entry with the same shortname is
+    /// replaced (losing its click count) instead of causing a `Conflict` error. Only a
+    /// live or scheduled entry causes one.
+    async fn reclaim_entry(&self, entry: &Entry) -> Result<()>;
+
+    /// Saves the entry's `longurl`, `updated_at`, and `version` over the stored entry,
+    /// leaving its other fields alone. Fails with a `Conflict` error if the stored
+    /// entry's version isn't `expected_version`, because someone else updated it first,
+    /// or with a `NotFound` error if there's no such entry.
+    async fn update_entry(&self, entry: &Entry, expected_version: u32) -> Result<()>;
+
+    /// Marks the entry as deleted at `deleted_at`, leaving its other fields alone, and
+    /// returns the updated entry. Fails with a `NotFound` error if there's no such
+    /// entry.
+    async fn delete_entry(&self, shortname: &str, deleted_at: DateTime<Utc>) -> Result<Entry>;
+
+    /// Sets the entry's click count, leaving its other fields alone. Fails with a
+    /// `NotFound` error if there's no such entry.
+    async fn set_clicks(&self, shortname: &str, clicks: u32) -> Result<()>;
+
+    /// Adds one to the entry's click count. This must be atomic, since shortlinks are
+    /// followed concurrently.
+    async fn record_click(&self, shortname: &str) -> Result<()>;
+
+    /// Saves one click on a shortlink to the click log.
+    async fn record_click_event(&self, event: &ClickEvent) -> Result<()>;
+
+    /// Returns the shortlink's logged clicks from `from` up to (but not including)
+    /// `to`, oldest first.
+    async fn click_events(
+        &self,
+        shortname: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<ClickEvent>>;
+}
@@ -0,0 +1,201 @@
+use chrono::{DateTime, Utc};
+use url::Url;
+
+use crate::error::ValidationErrors;
+
+/// The longest shortname allowed.
+pub(crate) const MAX_SHORTNAME_LENGTH: usize = 64;
+
+/// Shortnames that would be confusing, or that collide with the API's own paths on
+/// the shortlink domain. Compared without regard to case.
+const RESERVED_SHORTNAMES: &[&str] = &[
+    "admin",
+    "api",
+    "docs",
+    "guestbook",
+    "healthcheck",
+    "login",
+    "s",
+    "shortener",
+    "slack",
+    "stats",
+];
+
+/// The domain whose every path is a shortname: `SHORTLINK_HOST`, or `jil.im` when
+/// it isn't set.
+pub(crate) fn shortlink_host() -> String {
+    std::env::var("SHORTLINK_HOST").unwrap_or_else(|_| "jil.im".to_string())
+}
+
+/// Checks a new shortname against the slug rules and the reserved list.
+pub(crate) fn validate_shortname(errors: &mut ValidationErrors, shortname: &str) {
+    if shortname.is_empty() {
+        errors.push("shortname", "required", "Shortname must not be empty.");
+    } else if shortname.len() > MAX_SHORTNAME_LENGTH {
+        errors.push(
+            "shortname",
+            "too_long",
+            format!("Shortname must be at most {MAX_SHORTNAME_LENGTH} characters."),
+        );
+    } else if !shortname
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        errors.push(
+            "shortname",
+            "invalid_shortname",
+            "Shortname may only contain letters, numbers, dashes, and underscores.",
+        );
+    } else if RESERVED_SHORTNAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(shortname))
+    {
+        errors.push(
+            "shortname",
+            "reserved_shortname",
+            format!("Shortname {shortname} is reserved."),
+        );
+    }
+}
+
+/// Checks that a long URL is an absolute http(s) URL that doesn't lead back to a
+/// shortlink, which could redirect forever.
+pub(crate) fn validate_longurl(errors: &mut ValidationErrors, longurl: &str) {
+    if longurl.is_empty() {
+        errors.push("longurl", "required", "Long URL must not be empty.");
+        return;
+    }
+
+    let url = match Url::parse(longurl) {
+        Ok(url) if url.host_str().is_some() => url,
+        _ => {
+            errors.push(
+                "longurl",
+                "invalid_url",
+                "Long URL must be an absolute URL, like https://example.com.",
+            );
+            return;
+        }
+    };
+
+    if !matches!(url.scheme(), "http" | "https") {
+        errors.push(
+            "longurl",
+            "unsupported_scheme",
+            "Long URL must be an http or https link.",
+        );
+        return;
+    }
+
+    if points_at_a_shortlink(&url) {
+        errors.push(
+            "longurl",
+            "redirect_loop",
+            "Long URL can't point at another shortlink.",
+        );
+    }
+}
+
+/// Checks that a shortlink doesn't expire before it starts working, or before it's
+/// even created.
+pub(crate) fn validate_schedule(
+    errors: &mut ValidationErrors,
+    not_before: Option<DateTime<Utc>>,
+    expires_at: Option<DateTime<Utc>>,
+) {
+    let Some(expires_at) = expires_at else {
+        return;
+    };
+
+    if expires_at <= Utc::now() {
+        errors.push(
+            "expires_at",
+            "in_the_past",
+            "Expiry time must be in the future.",
+        );
+    } else if not_before.map_or(false, |not_before| not_before >= expires_at) {
+        errors.push(
+            "expires_at",
+            "expires_before_start",
+            "Expiry time must be after the not-before time.",
+        );
+    }
+}
+
+/// Whether the URL would be resolved as a shortlink: anything on the shortlink domain,
+/// or `/s/…` on the API's own domain.
+fn points_at_a_shortlink(url: &Url) -> bool {
+    let host = url.host_str().unwrap_or_default();
+    host.eq_ignore_ascii_case(&shortlink_host())
+        || (host.eq_ignore_ascii_case("api.jameslittle.me") && url.path().starts_with("/s/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shortname_codes(shortname: &str) -> Vec<&'static str> {
+        let mut errors = ValidationErrors::default();
+        validate_shortname(&mut errors, shortname);
+        errors.0.into_iter().map(|error| error.code).collect()
+    }
+
+    fn longurl_codes(longurl: &str) -> Vec<&'static str> {
+        let mut errors = ValidationErrors::default();
+        validate_longurl(&mut errors, longurl);
+        errors.0.into_iter().map(|error| error.code).collect()
+    }
+
+    #[test]
+    fn test_shortnames() {
+        assert!(shortname_codes("stork-search_2").is_empty());
+        assert_eq!(shortname_codes(""), vec!["required"]);
+        assert_eq!(shortname_codes("has space"), vec!["invalid_shortname"]);
+        assert_eq!(shortname_codes("ünïcode"), vec!["invalid_shortname"]);
+        assert_eq!(shortname_codes("Admin"), vec!["reserved_shortname"]);
+        assert_eq!(
+            shortname_codes(&"a".repeat(MAX_SHORTNAME_LENGTH + 1)),
+            vec!["too_long"]
+        );
+    }
+
+    #[test]
+    fn test_schedules() {
+        let codes = |not_before: Option<DateTime<Utc>>, expires_at: Option<DateTime<Utc>>| {
+            let mut errors = ValidationErrors::default();
+            validate_schedule(&mut errors, not_before, expires_at);
+            errors
+                .0
+                .into_iter()
+                .map(|error| error.code)
+                .collect::<Vec<_>>()
+        };
+
+        let now = Utc::now();
+        let day = chrono::Duration::days(1);
+        assert!(codes(None, None).is_empty());
+        assert!(codes(Some(now + day), None).is_empty());
+        assert!(codes(Some(now + day), Some(now + day * 2)).is_empty());
+        assert_eq!(codes(None, Some(now - day)), vec!["in_the_past"]);
+        assert_eq!(
+            codes(Some(now + day * 2), Some(now + day)),
+            vec!["expires_before_start"]
+        );
+    }
+
+    #[test]
+    fn test_longurls() {
+        assert!(longurl_codes("https://example.com/path?q=1").is_empty());
+        assert!(longurl_codes("https://api.jameslittle.me/guestbook").is_empty());
+        assert_eq!(longurl_codes("example.com"), vec!["invalid_url"]);
+        assert_eq!(
+            longurl_codes("ftp://example.com"),
+            vec!["unsupported_scheme"]
+        );
+        assert_eq!(longurl_codes("https://JIL.im/other"), vec!["redirect_loop"]);
+        assert_eq!(
+            longurl_codes("https://api.jameslittle.me/s/other"),
+            vec!["redirect_loop"]
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::Sha256;
+
+/// How old a signed request from Slack may be before we refuse it, to make replayed
+/// requests useless.
+const MAX_REQUEST_AGE_SECONDS: i64 = 60 * 5;
+
+/// Checks the `X-Slack-Signature` header Slack sends with every interaction request.
+///
+/// See <https://api.slack.com/authentication/verifying-requests-from-slack>.
+pub(crate) fn verify_signature(
+    signing_secret: &str,
+    timestamp: &str,
+    body: &[u8],
+    signature: &str,
+    now: i64,
+) -> bool {
+    let Ok(request_time) = timestamp.parse::<i64>() else {
+        return false;
+    };
+
+    if (now - request_time).abs() > MAX_REQUEST_AGE_SECONDS {
+        return false;
+    }
+
+    let Some(Ok(signature)) = signature.strip_prefix("v0=").map(hex::decode) else {
+        return false;
+    };
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(signing_secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(format!("v0:{timestamp}:").as_bytes());
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// The form body of an interaction request; the interesting part is JSON-encoded
+/// inside the `payload` field.
+#[derive(Debug, Deserialize)]
+pub(crate) struct InteractionForm {
+    pub payload: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct InteractionPayload {
+    #[serde(rename = "type")]
+    pub kind: String,
+
+    pub user: SlackUser,
+
+    #[serde(default)]
+    pub actions: Vec<BlockAction>,
+
+    pub response_url: Option<String>,
+
+    pub message: Option<InteractionMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SlackUser {
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct BlockAction {
+    pub action_id: String,
+
+    pub value: Option<String>,
+}
+
+/// The message that held the button that was pressed.
+#[derive(Debug, Deserialize)]
+pub(crate) struct InteractionMessage {
+    #[serde(default)]
+    pub text: String,
+
+    #[serde(default)]
+    pub blocks: Vec<Map<String, Value>>,
+}
+
+#[derive(Debug, Serialize)]
+struct MessageUpdate<'a> {
+    replace_original: bool,
+    text: &'a str,
+    blocks: &'a [Map<String, Value>],
+}
+
+/// Replaces the message an interaction came from, using the interaction's `response_url`.
+pub(crate) async fn update_message(
+    response_url: &str,
+    text: &str,
+    blocks: &[Map<String, Value>],
+) -> Result<reqwest::Response> {
+    let body = serde_json::to_string(&MessageUpdate {
+        replace_original: true,
+        text,
+        blocks,
+    })?;
+
+    reqwest::Client::new()
+        .post(response_url)
+        .header("Content-Type", "application/json")
+        .body(body)
+        .send()
+        .await
+        .map_err(|err| err.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(secret: &str, timestamp: &str, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(format!("v0:{timestamp}:").as_bytes());
+        mac.update(body);
+        format!("v0={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn test_verify_signature_accepts_valid_signature() {
+        let signature = sign("secret", "1700000000", b"payload=%7B%7D");
+        assert!(verify_signature(
+            "secret",
+            "1700000000",
+            b"payload=%7B%7D",
+            &signature,
+            1700000010
+        ));
+    }
+
+    #[test]
+    fn test_verify_signature_rejects_wrong_secret_or_body() {
+        let signature = sign("other secret", "1700000000", b"payload=%7B%7D");
+        assert!(!verify_signature(
+            "secret",
+            "1700000000",
+            b"payload=%7B%7D",
+            &signature,
+            1700000000
+        ));
+
+        let signature = sign("secret", "1700000000", b"payload=%7B%7D");
+        assert!(!verify_signature(
+            "secret",
+            "1700000000",
+            b"payload=tampered",
+            &signature,
+            1700000000
+        ));
+    }
+
+    #[test]
+    fn test_verify_signature_rejects_stale_requests() {
+        let signature = sign("secret", "1700000000", b"");
+        assert!(!verify_signature(
+            "secret",
+            "1700000000",
+            b"",
+            &signature,
+            1700000000 + MAX_REQUEST_AGE_SECONDS + 1
+        ));
+    }
+
+    #[test]
+    fn test_verify_signature_rejects_malformed_headers() {
+        assert!(!verify_signature("secret", "yesterday", b"", "v0=00", 0));
+        assert!(!verify_signature("secret", "0", b"", "v1=00", 0));
+        assert!(!verify_signature("secret", "0", b"", "v0=not hex", 0));
+    }
+}
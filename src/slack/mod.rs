@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 
 pub(crate) mod channel;
+pub(crate) mod interactions;
 use channel::SlackChannel;
 use utoipa::ToSchema;
 
@@ -0,0 +1,246 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use minijinja::{Environment, ErrorKind};
+use serde::Serialize;
+
+/// The templates compiled into the binary, used for any template missing from the
+/// templates directory.
+const EMBEDDED_TEMPLATES: &[(&str, &str)] = &[
+    (
+        "guestbook.html",
+        include_str!("../templates/guestbook.html"),
+    ),
+    (
+        "guestbook/entries.html",
+        include_str!("../templates/guestbook/entries.html"),
+    ),
+    (
+        "guestbook/errors.html",
+        include_str!("../templates/guestbook/errors.html"),
+    ),
+    (
+        "guestbook/feed.atom.xml",
+        include_str!("../templates/guestbook/feed.atom.xml"),
+    ),
+    (
+        "guestbook/feed.rss.xml",
+        include_str!("../templates/guestbook/feed.rss.xml"),
+    ),
+    (
+        "guestbook/feed_entry.html",
+        include_str!("../templates/guestbook/feed_entry.html"),
+    ),
+    (
+        "guestbook/form.html",
+        include_str!("../templates/guestbook/form.html"),
+    ),
+    (
+        "guestbook/success.html",
+        include_str!("../templates/guestbook/success.html"),
+    ),
+    (
+        "shortener/missing.html",
+        include_str!("../templates/shortener/missing.html"),
+    ),
+];
+
+/// The HTML templates the API renders.
+///
+/// Templates are read from a directory the first time they're rendered, so they can
+/// be changed without a rebuild. Any template that isn't in the directory falls back
+/// to the copy embedded in the binary. Templates ending in `.html` or `.xml` are
+/// HTML-escaped.
+#[derive(Debug)]
+pub(crate) struct Templates {
+    env: Environment<'static>,
+}
+
+impl Templates {
+    pub(crate) fn new(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        let mut env = Environment::new();
+        env.set_loader(move |name| load_template(&dir, name));
+        env.add_filter("relative_time", relative_time);
+        env.add_filter("markdown_escape", markdown_escape);
+        Self { env }
+    }
+
+    /// Reads templates from `TEMPLATES_DIR`, or from `./templates` when it isn't set.
+    pub(crate) fn from_env() -> Self {
+        Self::new(std::env::var("TEMPLATES_DIR").unwrap_or_else(|_| "templates".to_string()))
+    }
+
+    pub(crate) fn render(&self, name: &str, ctx: impl Serialize) -> Result<String> {
+        Ok(self.env.get_template(name)?.render(ctx)?)
+    }
+}
+
+fn load_template(dir: &Path, name: &str) -> Result<Option<String>, minijinja::Error> {
+    match fs::read_to_string(dir.join(name)) {
+        Ok(source) => Ok(Some(source)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(EMBEDDED_TEMPLATES
+            .iter()
+            .find(|(embedded_name, _)| *embedded_name == name)
+            .map(|(_, source)| source.to_string())),
+        Err(err) => Err(minijinja::Error::new(
+            ErrorKind::InvalidOperation,
+            format!("could not read template {name}"),
+        )
+        .with_source(err)),
+    }
+}
+
+/// Formats an RFC 3339 timestamp relative to now, like "5 minutes ago".
+fn relative_time(value: &str) -> Result<String, minijinja::Error> {
+    let time = DateTime::parse_from_rfc3339(value).map_err(|err| {
+        minijinja::Error::new(ErrorKind::InvalidOperation, "not an RFC 3339 timestamp")
+            .with_source(err)
+    })?;
+
+    Ok(relative_time_since(time.with_timezone(&Utc), Utc::now()))
+}
+
+fn relative_time_since(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let elapsed = now.signed_duration_since(time);
+
+    let plural = |count: i64, unit: &str| {
+        if count == 1 {
+            format!("1 {unit} ago")
+        } else {
+            format!("{count} {unit}s ago")
+        }
+    };
+
+    if elapsed.num_minutes() < 1 {
+        "just now".to_string()
+    } else if elapsed.num_hours() < 1 {
+        plural(elapsed.num_minutes(), "minute")
+    } else if elapsed.num_days() < 1 {
+        plural(elapsed.num_hours(), "hour")
+    } else if elapsed.num_days() < 2 {
+        "yesterday".to_string()
+    } else if elapsed.num_days() < 30 {
+        plural(elapsed.num_days(), "day")
+    } else {
+        time.format("%B %-d, %Y").to_string()
+    }
+}
+
+/// Backslash-escapes Markdown punctuation, so that text from a visitor renders as
+/// written when it ends up in a Markdown document.
+fn markdown_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "\\`*_{}[]()#+-.!|<>~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use minijinja::context;
+
+    use super::*;
+
+    #[test]
+    fn test_relative_time() {
+        let now = DateTime::parse_from_rfc3339("2024-08-14T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(relative_time_since(now, now), "just now");
+        assert_eq!(
+            relative_time_since(now + Duration::seconds(5), now),
+            "just now"
+        );
+        assert_eq!(
+            relative_time_since(now - Duration::minutes(1), now),
+            "1 minute ago"
+        );
+        assert_eq!(
+            relative_time_since(now - Duration::minutes(45), now),
+            "45 minutes ago"
+        );
+        assert_eq!(
+            relative_time_since(now - Duration::hours(3), now),
+            "3 hours ago"
+        );
+        assert_eq!(
+            relative_time_since(now - Duration::hours(30), now),
+            "yesterday"
+        );
+        assert_eq!(
+            relative_time_since(now - Duration::days(4), now),
+            "4 days ago"
+        );
+        assert_eq!(
+            relative_time_since(now - Duration::days(60), now),
+            "June 15, 2024"
+        );
+    }
+
+    #[test]
+    fn test_markdown_escape() {
+        assert_eq!(markdown_escape("hello"), "hello");
+        assert_eq!(
+            markdown_escape("*bold* [link](x)"),
+            "\\*bold\\* \\[link\\]\\(x\\)"
+        );
+    }
+
+    #[test]
+    fn test_embedded_templates_are_escaped() {
+        let templates = Templates::new("/nonexistent");
+        let rendered = templates
+            .render(
+                "guestbook/entries.html",
+                context! { entries => vec![context! {
+                    id => "1",
+                    name => "<b>lemon</b>",
+                    message => "hello",
+                    created_at => Utc::now().to_rfc3339(),
+                }] },
+            )
+            .unwrap();
+
+        assert!(rendered.contains("&lt;b&gt;lemon"));
+        assert!(rendered.contains("just now"));
+    }
+
+    #[test]
+    fn test_templates_directory_overrides_embedded() {
+        let dir = std::env::temp_dir().join(format!("jil-api-templates-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("guestbook")).unwrap();
+        fs::write(
+            dir.join("guestbook/errors.html"),
+            "{{ errors | length }} errors",
+        )
+        .unwrap();
+
+        let templates = Templates::new(dir.clone());
+        assert_eq!(
+            templates
+                .render(
+                    "guestbook/errors.html",
+                    context! { errors => vec!["a", "b"] }
+                )
+                .unwrap(),
+            "2 errors"
+        );
+        assert!(templates
+            .render("guestbook/form.html", context! { form => context! {} })
+            .unwrap()
+            .contains("<form"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
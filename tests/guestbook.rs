@@ -1,7 +1,7 @@
 mod test_utils;
 use serde_json::json;
 
-use crate::test_utils::spawn_app;
+use crate::test_utils::{spawn_app, ADMIN_TOKEN};
 
 #[tokio::test]
 async fn guestbook_post_takes_form_data() {
@@ -59,3 +59,618 @@ async fn guestbook_post_takes_json_data() {
     assert!(response_value.get("created_at").is_some());
     assert!(response_value.get("id").is_some());
 }
+
+async fn get_json(client: &reqwest::Client, url: &str) -> serde_json::Value {
+    let response = client.get(url).send().await.unwrap();
+    serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn guestbook_entries_are_readable_after_post() {
+    let address = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&format!("{}/guestbook", address))
+        .form(&[("name", "lemon"), ("message", "hello"), ("qa", "true")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let created =
+        serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    let id = created.get("id").unwrap().as_str().unwrap();
+
+    let single = get_json(&client, &format!("{}/guestbook/{}", address, id)).await;
+    assert_eq!(single.get("message").unwrap(), "hello");
+
+    let listed = get_json(&client, &format!("{}/guestbook", address)).await;
+    assert_eq!(listed.get("items").unwrap(), &json!([]));
+
+    let listed_with_qa = get_json(&client, &format!("{}/guestbook?qa=true", address)).await;
+    assert_eq!(
+        listed_with_qa.get("items").unwrap()[0].get("id").unwrap(),
+        id
+    );
+}
+
+#[tokio::test]
+async fn guestbook_pages_forward_and_backward() {
+    let address = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    for message in ["one", "two", "three"] {
+        client
+            .post(&format!("{}/guestbook", address))
+            .form(&[("name", "lemon"), ("message", message), ("qa", "true")])
+            .send()
+            .await
+            .unwrap();
+    }
+
+    let first = get_json(&client, &format!("{}/guestbook?qa=true&limit=2", address)).await;
+    assert_eq!(first.get("count").unwrap(), 2);
+    assert!(first.get("prev_cursor").unwrap().is_null());
+
+    let next_cursor = first.get("next_cursor").unwrap().as_str().unwrap();
+    let second = get_json(
+        &client,
+        &format!(
+            "{}/guestbook?qa=true&limit=2&cursor={}",
+            address, next_cursor
+        ),
+    )
+    .await;
+    assert_eq!(
+        second.get("items").unwrap()[0].get("message").unwrap(),
+        "three"
+    );
+    assert!(second.get("next_cursor").unwrap().is_null());
+
+    let prev_cursor = second.get("prev_cursor").unwrap().as_str().unwrap();
+    let back = get_json(
+        &client,
+        &format!(
+            "{}/guestbook?qa=true&limit=2&cursor={}",
+            address, prev_cursor
+        ),
+    )
+    .await;
+    assert_eq!(back.get("items").unwrap(), first.get("items").unwrap());
+}
+
+#[tokio::test]
+async fn guestbook_post_rejects_filled_honeypot() {
+    let address = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&format!("{}/guestbook", address))
+        .form(&[
+            ("name", "lemon"),
+            ("message", "hello"),
+            ("phone", "555-1234"),
+        ])
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let body = serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    assert_eq!(body.get("reason").unwrap(), "honeypot");
+}
+
+#[tokio::test]
+async fn guestbook_page_renders_entries() {
+    let address = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    client
+        .post(&format!("{}/guestbook", address))
+        .form(&[("name", "<b>lemon</b>"), ("message", "hello")])
+        .send()
+        .await
+        .unwrap();
+
+    let response = client
+        .get(&format!("{}/guestbook.html", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+
+    let body = response.text().await.unwrap();
+    assert!(body.contains("<form"));
+    assert!(body.contains("&lt;b&gt;lemon"));
+    assert!(body.contains("just now"));
+}
+
+#[tokio::test]
+async fn guestbook_post_from_htmx_renders_html() {
+    let address = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&format!("{}/guestbook", address))
+        .header("HX-Request", "true")
+        .form(&[("name", "lemon"), ("message", "hello"), ("qa", "true")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("Thanks for signing the guestbook, lemon!"));
+
+    let long_name = "a".repeat(601);
+    let response = client
+        .post(&format!("{}/guestbook", address))
+        .header("HX-Request", "true")
+        .form(&[("name", long_name.as_str()), ("message", "hello")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let body = response.text().await.unwrap();
+    assert!(body.contains("guestbook-errors"));
+    assert!(body.contains("Name must be"));
+    assert!(body.contains("hello"));
+
+    let response = client
+        .get(&format!("{}/guestbook/form", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert!(!response.text().await.unwrap().contains("guestbook-errors"));
+}
+
+#[tokio::test]
+async fn guestbook_feeds_support_conditional_requests() {
+    let address = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    client
+        .post(&format!("{}/guestbook", address))
+        .form(&[
+            ("name", "lemon"),
+            ("message", "hello"),
+            ("url", "https://example.com"),
+        ])
+        .send()
+        .await
+        .unwrap();
+
+    let response = client
+        .get(&format!("{}/guestbook/feed.atom", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("application/atom+xml"));
+
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+    let last_modified = response.headers()["last-modified"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body = response.text().await.unwrap();
+    assert!(body.contains("<name>lemon</name>"));
+    assert!(body.contains("<id>urn:uuid:"));
+
+    let response = client
+        .get(&format!("{}/guestbook/feed.atom", address))
+        .header("If-None-Match", &etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_MODIFIED);
+
+    let response = client
+        .get(&format!("{}/guestbook/feed.atom", address))
+        .header("If-Modified-Since", &last_modified)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_MODIFIED);
+
+    let response = client
+        .get(&format!("{}/guestbook/feed.rss", address))
+        .header("If-None-Match", &etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert!(response.text().await.unwrap().contains("<rss"));
+}
+
+#[tokio::test]
+async fn guestbook_replies_are_shown_with_entries() {
+    let address = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&format!("{}/guestbook", address))
+        .form(&[("name", "lemon"), ("message", "hello"), ("qa", "true")])
+        .send()
+        .await
+        .unwrap();
+    let created =
+        serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    assert!(created.get("reply").unwrap().is_null());
+    let id = created.get("id").unwrap().as_str().unwrap();
+
+    let unauthorized = client
+        .post(&format!("{}/guestbook/{}/reply", address, id))
+        .form(&[("text", "Thanks for stopping by!")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(unauthorized.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let replied = client
+        .post(&format!("{}/guestbook/{}/reply", address, id))
+        .bearer_auth(ADMIN_TOKEN)
+        .form(&[("text", "Thanks for stopping by!")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(replied.status(), reqwest::StatusCode::OK);
+
+    let single = get_json(&client, &format!("{}/guestbook/{}", address, id)).await;
+    assert_eq!(
+        single.get("reply").unwrap().get("text").unwrap(),
+        "Thanks for stopping by!"
+    );
+
+    let html = client
+        .get(&format!("{}/guestbook?qa=true&htmx=true", address))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(html.contains("Thanks for stopping by!"));
+}
+
+#[tokio::test]
+async fn guestbook_reactions_are_counted_once_per_visitor() {
+    let address = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&format!("{}/guestbook", address))
+        .form(&[("name", "lemon"), ("message", "hello"), ("qa", "true")])
+        .send()
+        .await
+        .unwrap();
+    let created =
+        serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    let id = created.get("id").unwrap().as_str().unwrap();
+
+    let react = || {
+        client
+            .post(&format!("{}/guestbook/{}/reactions", address, id))
+            .header("Content-Type", "application/json")
+            .body(json!({ "emoji": "👍" }).to_string())
+            .send()
+    };
+
+    let response = react().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let reacted =
+        serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    assert_eq!(reacted, json!({ "emoji": "👍", "count": 1 }));
+
+    let response = react().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::CONFLICT);
+
+    let response = client
+        .post(&format!("{}/guestbook/{}/reactions", address, id))
+        .header("Content-Type", "application/json")
+        .body(json!({ "emoji": "💩" }).to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let single = get_json(&client, &format!("{}/guestbook/{}", address, id)).await;
+    assert_eq!(single.get("reactions").unwrap(), &json!({ "👍": 1 }));
+}
+
+#[tokio::test]
+async fn guestbook_entries_can_be_edited_with_their_token() {
+    let address = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&format!("{}/guestbook", address))
+        .form(&[("name", "lemon"), ("message", "helo"), ("qa", "true")])
+        .send()
+        .await
+        .unwrap();
+    let created =
+        serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    let id = created.get("id").unwrap().as_str().unwrap();
+    let edit_token = created.get("edit_token").unwrap().as_str().unwrap();
+
+    let edit = |token: &str| {
+        client
+            .patch(&format!("{}/guestbook/{}", address, id))
+            .header("Content-Type", "application/json")
+            .body(
+                json!({
+                    "edit_token": token,
+                    "name": "lemon",
+                    "message": "hello",
+                })
+                .to_string(),
+            )
+            .send()
+    };
+
+    let response = edit("0123abcd").await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let response = edit(edit_token).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let response = edit(edit_token).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::CONFLICT);
+
+    let single = get_json(&client, &format!("{}/guestbook/{}", address, id)).await;
+    assert_eq!(single.get("message").unwrap(), "hello");
+    assert_eq!(single.get("qa").unwrap(), true);
+    assert!(single.get("edited_at").unwrap().is_string());
+    assert!(single.get("edit_token").is_none());
+}
+
+#[tokio::test]
+async fn guestbook_post_reports_every_invalid_field() {
+    let address = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&format!("{}/guestbook", address))
+        .form(&[
+            ("name", "   "),
+            ("message", "hello"),
+            ("email", "not an email"),
+            ("url", "javascript:alert(1)"),
+        ])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let body = serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    assert_eq!(body.get("reason").unwrap(), "invalid_fields");
+
+    let fields = body
+        .get("fields")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|field| {
+            (
+                field.get("field").unwrap().as_str().unwrap(),
+                field.get("code").unwrap().as_str().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        fields,
+        vec![
+            ("name", "required"),
+            ("email", "invalid_email"),
+            ("url", "unsupported_scheme"),
+        ]
+    );
+
+    let response = client
+        .post(&format!("{}/guestbook", address))
+        .form(&[
+            ("name", " lemon "),
+            ("message", "hello"),
+            ("url", "example.com"),
+            ("qa", "true"),
+        ])
+        .send()
+        .await
+        .unwrap();
+    let created =
+        serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    assert_eq!(created.get("name").unwrap(), "lemon");
+    assert_eq!(created.get("url").unwrap(), "https://example.com/");
+}
+
+#[tokio::test]
+async fn guestbook_avatars_never_reveal_the_email() {
+    let address = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&format!("{}/guestbook", address))
+        .form(&[
+            ("name", "lemon"),
+            ("message", "hello"),
+            ("email", "Lemon@Example.com"),
+            ("qa", "true"),
+        ])
+        .send()
+        .await
+        .unwrap();
+    let body = response.text().await.unwrap();
+    assert!(!body.to_lowercase().contains("lemon@example.com"));
+
+    let created = serde_json::from_str::<serde_json::Value>(&body).unwrap();
+    let avatar_url = created.get("avatar_url").unwrap().as_str().unwrap();
+    assert!(avatar_url.starts_with("https://gravatar.com/avatar/"));
+
+    let response = client
+        .post(&format!("{}/guestbook", address))
+        .form(&[("name", "lemon"), ("message", "hello"), ("qa", "true")])
+        .send()
+        .await
+        .unwrap();
+    let created =
+        serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    let id = created.get("id").unwrap().as_str().unwrap();
+    assert!(created
+        .get("avatar_url")
+        .unwrap()
+        .as_str()
+        .unwrap()
+        .ends_with(&format!("/guestbook/{}/avatar.svg", id)));
+
+    let response = client
+        .get(&format!("{}/guestbook/{}/avatar.svg", address, id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert_eq!(
+        response.headers().get("Content-Type").unwrap(),
+        "image/svg+xml"
+    );
+    assert!(response.text().await.unwrap().starts_with("<svg"));
+}
+
+#[tokio::test]
+async fn guestbook_exports_can_be_imported() {
+    let address = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&format!("{}/guestbook", address))
+        .form(&[
+            ("name", "lemon"),
+            ("message", "hello"),
+            ("email", "lemon@example.com"),
+            ("qa", "true"),
+        ])
+        .send()
+        .await
+        .unwrap();
+    let created =
+        serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    let id = created.get("id").unwrap().as_str().unwrap();
+
+    let response = client
+        .get(&format!("{}/admin/guestbook/export", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let response = client
+        .get(&format!("{}/admin/guestbook/export?format=csv", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let csv = response.text().await.unwrap();
+    assert!(csv.starts_with("id,created_at,"));
+    assert!(csv.contains("lemon@example.com"));
+
+    let response = client
+        .get(&format!("{}/admin/guestbook/export?format=jsonl", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    let export = response.text().await.unwrap();
+    let mut exported = serde_json::from_str::<serde_json::Value>(export.trim()).unwrap();
+    assert_eq!(exported.get("email").unwrap(), "lemon@example.com");
+
+    exported["message"] = json!("imported");
+    let import = |dry_run: bool| {
+        client
+            .post(&format!(
+                "{}/admin/guestbook/import?dry_run={}",
+                address, dry_run
+            ))
+            .bearer_auth(ADMIN_TOKEN)
+            .body(format!("{}\n", exported))
+            .send()
+    };
+
+    let report = serde_json::from_str::<serde_json::Value>(
+        &import(true).await.unwrap().text().await.unwrap(),
+    )
+    .unwrap();
+    assert_eq!(report.get("updated").unwrap(), &json!([id]));
+    let single = get_json(&client, &format!("{}/guestbook/{}", address, id)).await;
+    assert_eq!(single.get("message").unwrap(), "hello");
+
+    import(false).await.unwrap();
+    let single = get_json(&client, &format!("{}/guestbook/{}", address, id)).await;
+    assert_eq!(single.get("message").unwrap(), "imported");
+
+    let response = client
+        .post(&format!("{}/admin/guestbook/import", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .body("not json\n")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn guestbook_recount_is_admin_only() {
+    let address = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    for qa in ["false", "true"] {
+        client
+            .post(&format!("{}/guestbook", address))
+            .form(&[("name", "lemon"), ("message", "hello"), ("qa", qa)])
+            .send()
+            .await
+            .unwrap();
+    }
+
+    let response = client
+        .post(&format!("{}/admin/guestbook/recount", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let response = client
+        .post(&format!("{}/admin/guestbook/recount", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let counts =
+        serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    assert_eq!(counts.get("total").unwrap(), 2);
+    assert_eq!(counts.get("qa").unwrap(), 1);
+    assert_eq!(
+        get_json(&client, &format!("{}/guestbook?qa=true", address))
+            .await
+            .get("visible_total")
+            .unwrap(),
+        counts.get("published").unwrap().as_u64().unwrap()
+            + counts.get("published_qa").unwrap().as_u64().unwrap()
+    );
+}
@@ -0,0 +1,209 @@
+mod test_utils;
+use crate::test_utils::{spawn_app, ADMIN_TOKEN};
+
+async fn spawn_app_requiring_approval() -> String {
+    std::env::set_var("GUESTBOOK_REQUIRE_APPROVAL", "true");
+    spawn_app().await
+}
+
+async fn get_json(client: &reqwest::Client, url: &str) -> serde_json::Value {
+    let response = client.get(url).send().await.unwrap();
+    serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn pending_entries_are_listed_once_approved() {
+    let address = spawn_app_requiring_approval().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&format!("{}/guestbook", address))
+        .form(&[("name", "lemon"), ("message", "hello"), ("qa", "true")])
+        .send()
+        .await
+        .unwrap();
+    let created =
+        serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    assert_eq!(created.get("status").unwrap(), "pending");
+    let id = created.get("id").unwrap().as_str().unwrap();
+
+    let listed = get_json(&client, &format!("{}/guestbook?qa=true", address)).await;
+    assert_eq!(listed.get("count").unwrap(), 0);
+
+    let unauthorized = client
+        .post(&format!("{}/guestbook/{}/approve", address, id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(unauthorized.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let approved = client
+        .post(&format!("{}/guestbook/{}/approve", address, id))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(approved.status(), reqwest::StatusCode::OK);
+
+    let listed = get_json(&client, &format!("{}/guestbook?qa=true", address)).await;
+    assert_eq!(listed.get("count").unwrap(), 1);
+}
+
+#[tokio::test]
+async fn rejected_entries_are_not_listed() {
+    let address = spawn_app_requiring_approval().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&format!("{}/guestbook", address))
+        .form(&[("name", "lemon"), ("message", "hello"), ("qa", "true")])
+        .send()
+        .await
+        .unwrap();
+    let created =
+        serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    let id = created.get("id").unwrap().as_str().unwrap();
+
+    let rejected = client
+        .post(&format!("{}/guestbook/{}/reject", address, id))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    let rejected =
+        serde_json::from_str::<serde_json::Value>(&rejected.text().await.unwrap()).unwrap();
+    assert_eq!(rejected.get("status").unwrap(), "rejected");
+
+    let listed = get_json(&client, &format!("{}/guestbook?qa=true", address)).await;
+    assert_eq!(listed.get("count").unwrap(), 0);
+}
+
+#[tokio::test]
+async fn deleted_entries_can_be_restored_and_hard_deleted() {
+    let address = spawn_app_requiring_approval().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&format!("{}/guestbook", address))
+        .form(&[("name", "lemon"), ("message", "hello"), ("qa", "true")])
+        .send()
+        .await
+        .unwrap();
+    let created =
+        serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    let id = created.get("id").unwrap().as_str().unwrap();
+
+    for action in ["approve", "delete", "restore"] {
+        let response = client
+            .post(&format!("{}/guestbook/{}/{}", address, id, action))
+            .bearer_auth(ADMIN_TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+
+    let listed = get_json(&client, &format!("{}/guestbook?qa=true", address)).await;
+    assert_eq!(listed.get("count").unwrap(), 1);
+
+    let response = client
+        .delete(&format!("{}/guestbook/{}?hard=true", address, id))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let response = client
+        .get(&format!("{}/guestbook/{}", address, id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn admin_listing_filters_by_status_and_shows_email() {
+    let address = spawn_app_requiring_approval().await;
+    let client = reqwest::Client::new();
+
+    for name in ["lemon", "lime"] {
+        client
+            .post(&format!("{}/guestbook", address))
+            .form(&[
+                ("name", name),
+                ("message", "hello"),
+                ("email", "citrus@example.com"),
+            ])
+            .send()
+            .await
+            .unwrap();
+    }
+
+    let response = client
+        .get(&format!("{}/admin/guestbook?status=pending&q=LEM", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    let listed =
+        serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+
+    assert_eq!(listed.get("count").unwrap(), 1);
+    let entry = &listed.get("items").unwrap()[0];
+    assert_eq!(entry.get("name").unwrap(), "lemon");
+    assert_eq!(entry.get("email").unwrap(), "citrus@example.com");
+    assert!(entry.get("deleted_at").unwrap().is_null());
+
+    let response = client
+        .get(&format!("{}/admin/guestbook?status=deleted", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    let listed =
+        serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    assert_eq!(listed.get("count").unwrap(), 0);
+}
+
+#[tokio::test]
+async fn listing_counts_are_split_by_kind() {
+    let address = spawn_app_requiring_approval().await;
+    let client = reqwest::Client::new();
+
+    let mut ids = vec![];
+    for _ in 0..2 {
+        let response = client
+            .post(&format!("{}/guestbook", address))
+            .form(&[("name", "lemon"), ("message", "hello"), ("qa", "true")])
+            .send()
+            .await
+            .unwrap();
+        let created =
+            serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+        ids.push(created.get("id").unwrap().as_str().unwrap().to_string());
+    }
+
+    client
+        .post(&format!("{}/guestbook/{}/approve", address, ids[0]))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+
+    let public = get_json(&client, &format!("{}/guestbook?qa=true", address)).await;
+    assert_eq!(public.get("page_count").unwrap(), 1);
+    assert_eq!(public.get("visible_total").unwrap(), 1);
+    assert!(public.get("qa_total").is_none());
+    assert!(public.get("deleted_total").is_none());
+
+    let response = client
+        .get(&format!("{}/guestbook?qa=true", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    let admin = serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    assert_eq!(admin.get("qa_total").unwrap(), 2);
+    assert_eq!(admin.get("deleted_total").unwrap(), 0);
+}
@@ -0,0 +1,564 @@
+mod test_utils;
+use serde_json::json;
+
+use crate::test_utils::{spawn_app, ADMIN_TOKEN};
+
+fn client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap()
+}
+
+async fn create_entry(client: &reqwest::Client, address: &str, shortname: &str, longurl: &str) {
+    let response = client
+        .post(&format!("{}/shortener/entries", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .header("Content-Type", "application/json")
+        .body(json!({ "shortname": shortname, "longurl": longurl }).to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+}
+
+async fn clicks(client: &reqwest::Client, address: &str, shortname: &str) -> u64 {
+    let response = client
+        .get(&format!("{}/shortener/entries", address))
+        .send()
+        .await
+        .unwrap();
+    let body = serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    body.get("items")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|entry| entry.get("shortname").unwrap() == shortname)
+        .unwrap()
+        .get("clicks")
+        .unwrap()
+        .as_u64()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn shortlinks_redirect_and_count_clicks() {
+    let address = spawn_app().await;
+    let client = client();
+    create_entry(&client, &address, "stork", "https://stork-search.net").await;
+
+    let response = client
+        .get(&format!("{}/s/stork", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FOUND);
+    assert_eq!(
+        response.headers().get("Location").unwrap(),
+        "https://stork-search.net"
+    );
+    assert_eq!(clicks(&client, &address, "stork").await, 1);
+
+    let response = client
+        .get(&format!("{}/stork", address))
+        .header("Host", "jil.im")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FOUND);
+    assert_eq!(clicks(&client, &address, "stork").await, 2);
+}
+
+#[tokio::test]
+async fn missing_and_deleted_shortlinks_get_pages() {
+    let address = spawn_app().await;
+    let client = client();
+
+    let response = client
+        .get(&format!("{}/s/nothing-here", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    assert!(response.text().await.unwrap().contains("nothing-here"));
+
+    create_entry(&client, &address, "old", "https://example.com").await;
+    let response = client
+        .post(&format!("{}/shortener/entries/old/delete", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let response = client
+        .get(&format!("{}/s/old", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::GONE);
+}
+
+#[tokio::test]
+async fn shortlinks_can_be_updated() {
+    let address = spawn_app().await;
+    let client = client();
+    create_entry(&client, &address, "notes", "https://example.com/old").await;
+    client
+        .get(&format!("{}/s/notes", address))
+        .send()
+        .await
+        .unwrap();
+
+    let update = |body: serde_json::Value| {
+        client
+            .post(&format!("{}/shortener/entries/notes", address))
+            .bearer_auth(ADMIN_TOKEN)
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+            .send()
+    };
+
+    let response = update(json!({ "longurl": "https://example.com/new", "version": 0 }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let response = update(json!({ "longurl": "https://example.com/stale", "version": 0 }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::CONFLICT);
+
+    let response = client
+        .get(&format!("{}/shortener/entries/notes", address))
+        .send()
+        .await
+        .unwrap();
+    let entry = serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    assert_eq!(entry.get("longurl").unwrap(), "https://example.com/new");
+    assert_eq!(entry.get("version").unwrap(), 1);
+    assert_eq!(entry.get("clicks").unwrap(), 1);
+
+    let response = client
+        .post(&format!("{}/shortener/entries/notes", address))
+        .header("Content-Type", "application/json")
+        .body(json!({ "longurl": "https://example.com/sneaky" }).to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let response = client
+        .get(&format!("{}/shortener/entries/nothing-here", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn shortlinks_are_validated() {
+    let address = spawn_app().await;
+    let client = client();
+    create_entry(&client, &address, "Stork", "https://stork-search.net").await;
+
+    let create = |shortname: &str, longurl: &str| {
+        client
+            .post(&format!("{}/shortener/entries", address))
+            .bearer_auth(ADMIN_TOKEN)
+            .header("Content-Type", "application/json")
+            .body(json!({ "shortname": shortname, "longurl": longurl }).to_string())
+            .send()
+    };
+
+    let response = create("stork", "https://example.com").await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::CONFLICT);
+
+    for (path, status) in [
+        ("s/stork", reqwest::StatusCode::FOUND),
+        ("s/STORK", reqwest::StatusCode::FOUND),
+        ("shortener/entries/sToRk", reqwest::StatusCode::OK),
+    ] {
+        let response = client
+            .get(&format!("{}/{}", address, path))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status);
+    }
+
+    let response = create("admin", "https://jil.im/stork").await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let body = serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    let codes = body
+        .get("fields")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|field| field.get("code").unwrap().as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(codes, vec!["reserved_shortname", "redirect_loop"]);
+}
+
+#[tokio::test]
+async fn shortlinks_without_a_shortname_get_one_generated() {
+    let address = spawn_app().await;
+    let client = client();
+
+    let response = client
+        .post(&format!("{}/shortener/entries", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .header("Content-Type", "application/json")
+        .body(json!({ "longurl": "https://stork-search.net" }).to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let body = serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    let shortname = body.get("shortname").unwrap().as_str().unwrap();
+    assert_eq!(shortname.len(), 6);
+    assert!(shortname.chars().all(|c| c.is_ascii_alphanumeric()));
+
+    let response = client
+        .get(&format!("{}/s/{}", address, shortname))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FOUND);
+    assert_eq!(
+        response.headers().get("Location").unwrap(),
+        "https://stork-search.net"
+    );
+}
+
+#[tokio::test]
+async fn deleted_shortnames_must_be_reclaimed() {
+    let address = spawn_app().await;
+    let client = client();
+    create_entry(&client, &address, "old", "https://example.com/old").await;
+    client
+        .get(&format!("{}/s/old", address))
+        .send()
+        .await
+        .unwrap();
+    client
+        .post(&format!("{}/shortener/entries/old/delete", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+
+    let create = |body: serde_json::Value| {
+        client
+            .post(&format!("{}/shortener/entries", address))
+            .bearer_auth(ADMIN_TOKEN)
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+            .send()
+    };
+
+    let response = create(json!({ "shortname": "old", "longurl": "https://example.com/new" }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::CONFLICT);
+    let body = serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    assert_eq!(body.get("reason").unwrap(), "shortname_deleted");
+
+    let response = create(json!({
+        "shortname": "old",
+        "longurl": "https://example.com/new",
+        "reclaim": true
+    }))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert_eq!(clicks(&client, &address, "old").await, 0);
+
+    let response = create(json!({
+        "shortname": "old",
+        "longurl": "https://example.com/newer",
+        "reclaim": true
+    }))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::CONFLICT);
+    let body = serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    assert_eq!(body.get("reason").unwrap(), "shortname_taken");
+}
+
+#[tokio::test]
+async fn concurrent_creates_of_a_shortname_only_succeed_once() {
+    let address = spawn_app().await;
+    let client = client();
+
+    let create = |longurl: &str| {
+        client
+            .post(&format!("{}/shortener/entries", address))
+            .bearer_auth(ADMIN_TOKEN)
+            .header("Content-Type", "application/json")
+            .body(json!({ "shortname": "race", "longurl": longurl }).to_string())
+            .send()
+    };
+
+    let (first, second) = tokio::join!(
+        create("https://example.com/first"),
+        create("https://example.com/second")
+    );
+    let mut statuses = vec![first.unwrap().status(), second.unwrap().status()];
+    statuses.sort();
+    assert_eq!(
+        statuses,
+        vec![reqwest::StatusCode::OK, reqwest::StatusCode::CONFLICT]
+    );
+}
+
+#[tokio::test]
+async fn shortlinks_only_work_between_not_before_and_expires_at() {
+    let address = spawn_app().await;
+    let client = client();
+    let now = chrono::Utc::now();
+
+    let response = client
+        .post(&format!("{}/shortener/entries", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .header("Content-Type", "application/json")
+        .body(
+            json!({
+                "shortname": "rsvp",
+                "longurl": "https://example.com/rsvp",
+                "not_before": now + chrono::Duration::days(1),
+                "expires_at": now + chrono::Duration::days(2),
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let response = client
+        .get(&format!("{}/s/rsvp", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+    let response = client
+        .post(&format!("{}/shortener/entries", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .header("Content-Type", "application/json")
+        .body(
+            json!({
+                "shortname": "party",
+                "longurl": "https://example.com/party",
+                "expires_at": now + chrono::Duration::seconds(1),
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+
+    let response = client
+        .get(&format!("{}/s/party", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::GONE);
+
+    let response = client
+        .get(&format!("{}/shortener/entries", address))
+        .send()
+        .await
+        .unwrap();
+    let body = serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    assert!(body.get("items").unwrap().as_array().unwrap().is_empty());
+
+    let response = client
+        .post(&format!("{}/shortener/entries", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .header("Content-Type", "application/json")
+        .body(
+            json!({
+                "shortname": "late",
+                "longurl": "https://example.com/late",
+                "expires_at": now - chrono::Duration::days(1),
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn shortlink_click_counts_can_be_set() {
+    let address = spawn_app().await;
+    let client = client();
+    create_entry(&client, &address, "stork", "https://stork-search.net").await;
+
+    let update = |body: serde_json::Value| {
+        client
+            .post(&format!("{}/shortener/stats", address))
+            .bearer_auth(ADMIN_TOKEN)
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+            .send()
+    };
+
+    let response = update(json!([{
+        "shortname": "stork",
+        "longurl": "https://example.com/overwritten",
+        "clicks": 7,
+    }]))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert_eq!(clicks(&client, &address, "stork").await, 7);
+
+    let response = client
+        .get(&format!("{}/s/stork", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        response.headers().get("Location").unwrap(),
+        "https://stork-search.net"
+    );
+
+    let response = update(json!([{ "shortname": "missing", "clicks": 1 }]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn shortlink_stats_summarize_clicks() {
+    let address = spawn_app().await;
+    let client = client();
+    create_entry(&client, &address, "stork", "https://stork-search.net").await;
+
+    // Ask for two days, so the clicks are counted even if the day ends in the meantime.
+    let from = chrono::Utc::now().naive_utc().date();
+    let stats_url = format!(
+        "{}/shortener/entries/stork/stats?from={}&to={}",
+        address,
+        from,
+        from + chrono::Duration::days(1)
+    );
+
+    for user_agent in [
+        "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0) Mobile/15E148",
+        "curl/8.4.0",
+    ] {
+        client
+            .get(&format!("{}/s/stork", address))
+            .header("Referer", "https://news.ycombinator.com/item?id=1")
+            .header("User-Agent", user_agent)
+            .send()
+            .await
+            .unwrap();
+    }
+
+    // Clicks are logged in the background, after the redirect.
+    let mut stats = serde_json::Value::Null;
+    for _ in 0..50 {
+        let response = client
+            .get(&stats_url)
+            .bearer_auth(ADMIN_TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        stats = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        if stats.get("total").unwrap() == 2 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+
+    assert_eq!(stats.get("total").unwrap(), 2);
+    let daily = stats.get("daily").unwrap().as_array().unwrap();
+    assert_eq!(daily.len(), 2);
+    assert_eq!(
+        daily
+            .iter()
+            .map(|day| day.get("clicks").unwrap().as_u64().unwrap())
+            .sum::<u64>(),
+        2
+    );
+    assert_eq!(
+        stats.get("top_referrers").unwrap(),
+        &json!([{ "name": "news.ycombinator.com", "clicks": 2 }])
+    );
+    assert_eq!(
+        stats.get("user_agents").unwrap(),
+        &json!({ "mobile": 1, "bot": 1 })
+    );
+
+    let response = client
+        .get(&format!(
+            "{}/shortener/entries/stork/stats?from=2026-10-18&to=2026-10-01",
+            address
+        ))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let response = client
+        .get(&format!("{}/shortener/entries/stork/stats", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    let stats = serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    assert_eq!(stats.get("daily").unwrap().as_array().unwrap().len(), 30);
+
+    let response = client
+        .get(&format!("{}/shortener/entries/stork/stats", address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    // A reclaimed shortname's stats start over, like its click count.
+    client
+        .post(&format!("{}/shortener/entries/stork/delete", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    let response = client
+        .post(&format!("{}/shortener/entries", address))
+        .bearer_auth(ADMIN_TOKEN)
+        .header("Content-Type", "application/json")
+        .body(
+            json!({
+                "shortname": "stork",
+                "longurl": "https://stork-search.net/docs",
+                "reclaim": true,
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let response = client
+        .get(&stats_url)
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    let stats = serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    assert_eq!(stats.get("total").unwrap(), 0);
+}
@@ -0,0 +1,81 @@
+mod test_utils;
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+
+use crate::test_utils::{spawn_app, SLACK_SIGNING_SECRET};
+
+fn signed_interaction(
+    client: &reqwest::Client,
+    address: &str,
+    payload: serde_json::Value,
+    secret: &str,
+) -> reqwest::RequestBuilder {
+    let body = serde_urlencoded::to_string([("payload", payload.to_string())]).unwrap();
+    let timestamp = chrono::Utc::now().timestamp().to_string();
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(format!("v0:{timestamp}:{body}").as_bytes());
+    let signature = format!("v0={}", hex::encode(mac.finalize().into_bytes()));
+
+    client
+        .post(&format!("{}/slack/interactions", address))
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .header("X-Slack-Request-Timestamp", timestamp)
+        .header("X-Slack-Signature", signature)
+        .body(body)
+}
+
+fn delete_payload(id: &str) -> serde_json::Value {
+    json!({
+        "type": "block_actions",
+        "user": { "id": "U123" },
+        "actions": [{ "action_id": "guestbook_delete", "value": id }]
+    })
+}
+
+async fn create_entry(client: &reqwest::Client, address: &str) -> String {
+    let response = client
+        .post(&format!("{}/guestbook", address))
+        .form(&[("name", "lemon"), ("message", "hello"), ("qa", "true")])
+        .send()
+        .await
+        .unwrap();
+    let created =
+        serde_json::from_str::<serde_json::Value>(&response.text().await.unwrap()).unwrap();
+    created.get("id").unwrap().as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn delete_button_deletes_entry() {
+    let address = spawn_app().await;
+    let client = reqwest::Client::new();
+    let id = create_entry(&client, &address).await;
+
+    let response = signed_interaction(&client, &address, delete_payload(&id), SLACK_SIGNING_SECRET)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    let entry = client
+        .get(&format!("{}/guestbook/{}", address, id))
+        .send()
+        .await
+        .unwrap();
+    let entry = serde_json::from_str::<serde_json::Value>(&entry.text().await.unwrap()).unwrap();
+    assert!(!entry.get("deleted_at").unwrap().is_null());
+}
+
+#[tokio::test]
+async fn unsigned_interactions_are_rejected() {
+    let address = spawn_app().await;
+    let client = reqwest::Client::new();
+    let id = create_entry(&client, &address).await;
+
+    let response = signed_interaction(&client, &address, delete_payload(&id), "wrong secret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
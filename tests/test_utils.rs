@@ -2,7 +2,20 @@ use std::net::TcpListener;
 
 use jil_api::run;
 
+/// The bearer token test servers accept on admin endpoints.
+#[allow(dead_code)]
+pub const ADMIN_TOKEN: &str = "test-admin-token";
+
+/// The secret test servers expect Slack interaction requests to be signed with.
+#[allow(dead_code)]
+pub const SLACK_SIGNING_SECRET: &str = "test-slack-signing-secret";
+
 pub async fn spawn_app() -> String {
+    std::env::set_var("STORAGE_BACKEND", "memory");
+    std::env::set_var("ADMIN_BEARER_TOKEN", ADMIN_TOKEN);
+    std::env::set_var("SLACK_SIGNING_SECRET", SLACK_SIGNING_SECRET);
+    // Only used to look up where clicks come from, which fails quietly in tests.
+    std::env::set_var("IPINFO_KEY", "test-ipinfo-key");
     let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind to port");
     let port = listener.local_addr().unwrap().port();
     let server = run(listener).await.expect("Failed to bind address");